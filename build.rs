@@ -3,6 +3,11 @@ use clap_generate::{
     generators::{Bash, Elvish, Fish, PowerShell, Zsh},
 };
 
+// only the parts used by the argument validators are needed here
+#[allow(dead_code)]
+#[path = "src/date.rs"]
+mod date;
+
 include!("src/command/app.rs");
 
 fn main() {
@@ -0,0 +1,273 @@
+//! Command aliases defined in the config file, such as `urgent = "list -l 5:"`.
+
+use clap::{App, ArgSettings};
+
+use std::collections::BTreeMap;
+
+/// Returns the names and the aliases of the subcommands of `app`.
+pub fn builtins(app: &App) -> Vec<String> {
+    let mut names = vec!["help".to_owned()];
+    for sc in app.get_subcommands() {
+        names.push(sc.get_name().to_owned());
+        names.extend(sc.get_all_aliases().map(String::from));
+    }
+    names
+}
+
+/// Returns a warning for each alias that has the name of a built-in command; they're ignored,
+/// so a command added to todo doesn't break the config files that already use its name for an alias.
+pub fn warnings(aliases: &BTreeMap<String, String>, builtins: &[String]) -> Vec<String> {
+    aliases
+        .keys()
+        .filter(|name| builtins.contains(name))
+        .map(|name| {
+            format!(
+                "the alias {} in the config file is ignored, it's also the name or an alias of a built-in command",
+                name
+            )
+        })
+        .collect()
+}
+
+/// Returns the index of the subcommand in `args`, the first argument that isn't an option of `app` or its value.
+pub fn position(app: &App, args: &[String]) -> Option<usize> {
+    // the options given as --name=value or -nvalue have their value in the same argument
+    let takes_value: Vec<String> = app
+        .get_arguments()
+        .filter(|a| a.is_set(ArgSettings::TakesValue))
+        .flat_map(|a| {
+            let long = a.get_long().map(|s| format!("--{}", s));
+            let short = a.get_short().map(|c| format!("-{}", c));
+            long.into_iter().chain(short)
+        })
+        .collect();
+
+    let mut i = 1;
+    while i < args.len() {
+        if !args[i].starts_with('-') {
+            return Some(i);
+        }
+        if takes_value.contains(&args[i]) {
+            i += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Replaces the alias at `args[i]` with its expansion, until `args[i]` is not an alias.
+pub fn expand(
+    mut args: Vec<String>,
+    i: usize,
+    aliases: &BTreeMap<String, String>,
+    builtins: &[String],
+) -> Result<Vec<String>, String> {
+    let mut seen: Vec<String> = Vec::new();
+    while let Some(value) = args.get(i).and_then(|name| {
+        if builtins.contains(name) {
+            None
+        } else {
+            aliases.get(name)
+        }
+    }) {
+        let name = args[i].clone();
+        if seen.contains(&name) {
+            seen.push(name);
+            return Err(format!(
+                "recursive alias in the config file: {}",
+                seen.join(" -> ")
+            ));
+        }
+        let words =
+            split(value).map_err(|e| format!("the alias {} in the config file: {}", name, e))?;
+        if words.is_empty() {
+            return Err(format!("the alias {} in the config file is empty", name));
+        }
+        seen.push(name);
+        args.splice(i..=i, words);
+    }
+    Ok(args)
+}
+
+/// Returns the names of the aliases along with their help, for [super::app::app_with],
+/// leaving out the ones that have the name of a built-in command.
+///
+/// clap borrows the strings for 'static, so they're leaked; this is done once, before parsing the arguments.
+pub fn subcommands(
+    aliases: &BTreeMap<String, String>,
+    builtins: &[String],
+) -> Vec<(&'static str, &'static str)> {
+    let leak = |s: String| -> &'static str { Box::leak(s.into_boxed_str()) };
+    aliases
+        .iter()
+        .filter(|(name, _)| !builtins.contains(name))
+        .map(|(name, value)| (leak(name.clone()), leak(format!("alias for `{}`", value))))
+        .collect()
+}
+
+/// Splits `s` into words like a shell: words are separated by whitespace,
+/// quotes group words together and a backslash escapes the next character outside single quotes.
+fn split(s: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // whether a word was started, to keep empty quoted words
+    let mut started = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                started = true;
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => {
+                            word.push(chars.next().ok_or("the value ends with a backslash")?)
+                        }
+                        Some(x) => word.push(x),
+                        None => return Err(format!("unclosed quote {}", c)),
+                    }
+                }
+            }
+            '\\' => {
+                started = true;
+                word.push(chars.next().ok_or("the value ends with a backslash")?);
+            }
+            _ if c.is_whitespace() => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                    started = false;
+                }
+            }
+            _ => {
+                started = true;
+                word.push(c);
+            }
+        }
+    }
+    if started {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::command::app;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn expand_args(
+        args: &[&str],
+        aliases: &BTreeMap<String, String>,
+    ) -> Result<Vec<String>, String> {
+        let builtins = builtins(&app::app());
+        let args = strings(args);
+        let i = position(&app::app(), &args).unwrap();
+        expand(args, i, aliases, &builtins)
+    }
+
+    #[test]
+    fn split_like_a_shell() {
+        assert_eq!(
+            split("list  -l 5:\t--sort -lvl").unwrap(),
+            ["list", "-l", "5:", "--sort", "-lvl"]
+        );
+        assert_eq!(
+            split(r#"list "buy milk" 'a "b"'"#).unwrap(),
+            ["list", "buy milk", r#"a "b""#]
+        );
+        assert_eq!(
+            split(r#"new "say \"hi\"" 'c:\dir'"#).unwrap(),
+            ["new", r#"say "hi""#, r"c:\dir"]
+        );
+        assert_eq!(
+            split(r"list buy\ milk \\").unwrap(),
+            ["list", "buy milk", r"\"]
+        );
+        assert_eq!(split(r#"list "" -t''x"#).unwrap(), ["list", "", "-tx"]);
+        assert!(split("   ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn split_errors() {
+        assert_eq!(split(r#"list "milk"#).unwrap_err(), "unclosed quote \"");
+        assert_eq!(split("list 'milk").unwrap_err(), "unclosed quote '");
+        assert!(split(r"list milk\").unwrap_err().contains("backslash"));
+    }
+
+    #[test]
+    fn aliases_expand_recursively() {
+        let a = aliases(&[
+            ("urgent", "important -l 5:"),
+            ("important", "list --sort -lvl"),
+        ]);
+        assert_eq!(
+            expand_args(&["todo", "-L", "work", "urgent", "milk"], &a).unwrap(),
+            ["todo", "-L", "work", "list", "--sort", "-lvl", "-l", "5:", "milk"]
+        );
+        // only the subcommand is expanded
+        assert_eq!(
+            expand_args(&["todo", "list", "urgent"], &a).unwrap(),
+            ["todo", "list", "urgent"]
+        );
+    }
+
+    #[test]
+    fn recursive_aliases_are_an_error() {
+        let a = aliases(&[("a", "b -x"), ("b", "c"), ("c", "a"), ("self", "self")]);
+        assert_eq!(
+            expand_args(&["todo", "a"], &a).unwrap_err(),
+            "recursive alias in the config file: a -> b -> c -> a"
+        );
+        assert_eq!(
+            expand_args(&["todo", "self"], &a).unwrap_err(),
+            "recursive alias in the config file: self -> self"
+        );
+        let a = aliases(&[("empty", " "), ("bad", "list 'x")]);
+        assert!(expand_args(&["todo", "empty"], &a)
+            .unwrap_err()
+            .contains("is empty"));
+        assert!(expand_args(&["todo", "bad"], &a)
+            .unwrap_err()
+            .contains("unclosed quote"));
+    }
+
+    #[test]
+    fn aliases_shadowing_builtins_are_ignored() {
+        let builtins = builtins(&app::app());
+        let a = aliases(&[("ls", "list -a"), ("new", "list"), ("urgent", "list -l 5:")]);
+        assert_eq!(
+            warnings(&a, &builtins),
+            [
+                "the alias ls in the config file is ignored, it's also the name or an alias of a built-in command",
+                "the alias new in the config file is ignored, it's also the name or an alias of a built-in command",
+            ]
+        );
+        let names: Vec<_> = subcommands(&a, &builtins).iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, ["urgent"]);
+        // the built-in command runs, not the alias
+        assert_eq!(expand_args(&["todo", "ls"], &a).unwrap(), ["todo", "ls"]);
+    }
+
+    #[test]
+    fn position_skips_the_global_options() {
+        let app = app::app();
+        let pos = |args: &[&str]| position(&app, &strings(args));
+        assert_eq!(pos(&["todo", "urgent"]), Some(1));
+        assert_eq!(pos(&["todo", "-L", "work", "urgent"]), Some(3));
+        assert_eq!(pos(&["todo", "--list=work", "-G", "urgent"]), Some(3));
+        assert_eq!(pos(&["todo", "-L", "work"]), None);
+    }
+}
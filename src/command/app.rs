@@ -55,8 +55,66 @@ fn validate_minmax(s: &str) -> Result<(), String> {
 	}
 }
 
+fn validate_id(s: &str) -> Result<(), String> {
+    if s.is_empty() {
+        Err("the id can't be empty".to_owned())
+    } else if s.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(format!("{}: ids can only contain hexadecimal digits", s))
+    }
+}
+
+fn validate_past(s: &str) -> Result<(), String> {
+    crate::date::parse_past(s, chrono::Local::now())
+        .map(|_| ())
+        .ok_or_else(|| {
+            format!(
+                "{}: expected a date (YYYY-MM-DD [HH:MM]) or a duration such as 3d or 2w",
+                s
+            )
+        })
+}
+
+fn validate_due(s: &str) -> Result<(), String> {
+    crate::date::parse_due(s, chrono::Local::now())
+        .map(|_| ())
+        .ok_or_else(|| {
+            format!(
+                "{}: expected a date (YYYY-MM-DD [HH:MM]), today, tomorrow, a weekday or a duration such as +3d",
+                s
+            )
+        })
+}
+
+fn validate_duration(s: &str) -> Result<(), String> {
+    crate::date::parse_duration(s)
+        .map(|_| ())
+        .ok_or_else(|| format!("{}: expected a duration such as 12h, 3d or 2w", s))
+}
+
+fn validate_sort(s: &str) -> Result<(), String> {
+    let name = s.trim_start_matches(['-', '+']);
+    match &name.to_lowercase()[..] {
+        "index" | "title" | "lvl" | "level" | "tag" | "tags" | "created" | "updated" | "due"
+        | "done" => Ok(()),
+        _ => Err(format!(
+            "{}: unknown sort key, possible values are index, title, lvl, tag, created, updated, due and done",
+            s
+        )),
+    }
+}
+
 pub fn app() -> App<'static> {
-    App::new("todo")
+    app_with(&[], &[])
+}
+
+/// Returns the app with the views and the aliases defined in the config file.
+///
+/// The view names are the possible values of `todo view`, for the shell completions;
+/// the aliases are pairs of a name and its help, and are listed among the subcommands.
+pub fn app_with(views: &[&'static str], aliases: &[(&'static str, &'static str)]) -> App<'static> {
+    let app = App::new("todo")
         .about("simple note tracker")
         .author("Taylan Gökkaya <github.com/insomnimus>")
         .global_setting(AppSettings::UnifiedHelpMessage)
@@ -64,18 +122,66 @@ pub fn app() -> App<'static> {
         .global_setting(AppSettings::AllowNegativeNumbers)
         .global_setting(AppSettings::DeriveDisplayOrder)
         .version(crate_version!())
+        .args(args_global())
         .subcommand(app_list())
         .subcommand(app_new())
         .subcommand(app_remove())
+        .subcommand(app_edit())
+        .subcommand(app_done())
+        .subcommand(app_reopen())
+        .subcommand(app_undo())
+        .subcommand(app_redo())
+        .subcommand(app_history())
         .subcommand(app_where())
         .subcommand(app_random())
+        .subcommand(app_search())
+        .subcommand(app_view(views))
+        .subcommand(app_lists())
+        .subcommand(app_move())
+        .subcommand(app_git())
+        .subcommand(app_completions())
         .after_long_help(
             "\
 todo checks for these env variables:
 -	TODO_CONFIG_DIR: the path of the directory that contains the todo.toml file
 -	TODOS_FILE_PATH: the absolute path to the file that todos are saved to (filename must be included)
+-	TODO_LIST: the name of the todo list to use, unless --list is given
+-	TODO_GLOBAL: if set to anything but the empty string, acts as --global
+-	VISUAL, EDITOR: the editor to compose and edit notes in
+-	NO_COLOR: disables colors unless --color=always is given
 ",
-        )
+        );
+
+    aliases.iter().fold(app, |app, (name, help)| {
+        app.subcommand(App::new(*name).about(*help))
+    })
+}
+
+/// Returns the `--list` and `--global` options, which every subcommand takes.
+pub fn args_global() -> Vec<Arg<'static>> {
+    let list = Arg::new("list")
+        .short('L')
+        .long("list")
+        .takes_value(true)
+        .global(true)
+        .about("the todo list to use, as named in the config file")
+        .long_about(
+            "the todo list to use, as named in the config file
+	the list of the todos_file is named default
+	overrides the default_list in the config file and the TODO_LIST env variable
+	the project todos file, the closest .todos.toml to the current directory, is named project",
+        );
+
+    let global = Arg::new("global")
+        .short('G')
+        .long("global")
+        .global(true)
+        .conflicts_with("list")
+        .about(
+            "don't use the project todos file, the closest .todos.toml to the current directory",
+        );
+
+    vec![list, global]
 }
 
 pub fn app_list() -> App<'static> {
@@ -125,7 +231,117 @@ pub fn app_list() -> App<'static> {
         .about("comma separated list of tags to filter the results with")
         .setting(ArgSettings::UseValueDelimiter);
 
-    app.arg(title).arg(index).arg(lvl).arg(tag)
+    let id = Arg::new("id")
+        .long("id")
+        .takes_value(true)
+        .about("comma separated list of note ids to filter the results with")
+        .long_about(
+            "comma separated list of note ids to filter the results with
+	an id can be shortened to any prefix that matches only one note",
+        )
+        .validator(validate_id)
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let done = Arg::new("done")
+        .short('d')
+        .long("done")
+        .about("display only the completed notes")
+        .conflicts_with("all");
+
+    let all = Arg::new("all")
+        .short('a')
+        .long("all")
+        .about("display completed notes as well");
+
+    let since = Arg::new("since")
+        .long("since")
+        .takes_value(true)
+        .about("display notes created at or after the given time")
+        .long_about(
+            "display notes created at or after the given time
+	the time can be a date: YYYY-MM-DD, YYYY-MM-DD HH:MM, today or yesterday
+	or a duration relative to now: 30m, 5h, 3d, 2w",
+        )
+        .validator(validate_past);
+
+    let before = Arg::new("before")
+        .long("before")
+        .takes_value(true)
+        .about("display notes created before the given time")
+        .long_about(
+            "display notes created before the given time
+	the time can be a date: YYYY-MM-DD, YYYY-MM-DD HH:MM, today or yesterday
+	or a duration relative to now: 30m, 5h, 3d, 2w",
+        )
+        .validator(validate_past);
+
+    let sort = Arg::new("sort")
+        .short('s')
+        .long("sort")
+        .takes_value(true)
+        .about("sort the results by a comma separated list of keys, such as -lvl,title")
+        .long_about(
+            "sort the results by a comma separated list of keys, such as -lvl,title
+	possible keys: index, title, lvl, tag, created, updated, due, done
+	later keys break the ties of the earlier ones
+	prefix a key with - to sort in descending order
+	notes without tags, a due date or a done date come last when sorting by them
+	the notes keep their index, so it can still be used with other commands",
+        )
+        .setting(ArgSettings::AllowHyphenValues)
+        .setting(ArgSettings::UseValueDelimiter)
+        .validator(validate_sort);
+
+    let overdue = Arg::new("overdue")
+        .long("overdue")
+        .about("display only the notes that are past their due date")
+        .conflicts_with("due-within");
+
+    let due_within = Arg::new("due-within")
+        .long("due-within")
+        .takes_value(true)
+        .about("display only the notes due within the given duration, such as 3d or 2w")
+        .long_about(
+            "display only the notes due within the given duration
+	the duration is a number followed by a unit: m (minutes), h, d or w
+	overdue notes are included",
+        )
+        .validator(validate_duration);
+
+    let search = Arg::new("search")
+        .long("search")
+        .takes_value(true)
+        .about("display only the notes whose title, body or tags contain the given text")
+        .long_about(
+            "display only the notes whose title, body or tags contain the given text
+	the matches are highlighted
+	see `todo search` to rank the notes by relevance",
+        );
+
+    let time = Arg::new("time")
+        .short('T')
+        .long("time")
+        .about("display the creation and modification times of the notes");
+
+    app.arg(title)
+        .arg(index)
+        .arg(id)
+        .arg(lvl)
+        .arg(tag)
+        .arg(done)
+        .arg(all)
+        .arg(since)
+        .arg(before)
+        .arg(overdue)
+        .arg(due_within)
+        .arg(sort)
+        .arg(time)
+        .arg(search)
+        .args(args_search_mode(Some("search")))
+        .arg(arg_query("display only the notes matching a query"))
+        .arg(arg_format())
+        .arg(arg_template())
+        .arg(arg_color())
 }
 
 pub fn app_remove() -> App<'static> {
@@ -174,7 +390,402 @@ pub fn app_remove() -> App<'static> {
         .about("comma separated list of tags to remove matching notes by")
         .setting(ArgSettings::UseValueDelimiter);
 
-    app.arg(title).arg(index).arg(lvl).arg(tag)
+    let id = Arg::new("id")
+        .long("id")
+        .takes_value(true)
+        .about("comma separated list of ids of the notes to remove")
+        .long_about(
+            "comma separated list of ids of the notes to remove
+	unlike the index, the id of a note never changes
+	an id can be shortened to any prefix that matches only one note",
+        )
+        .validator(validate_id)
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let dry_run = Arg::new("dry-run")
+        .short('n')
+        .long("dry-run")
+        .about("display the notes that would be removed without removing them");
+
+    let yes = Arg::new("yes")
+        .short('y')
+        .long("yes")
+        .about("do not ask for confirmation")
+        .long_about(
+            "do not ask for confirmation
+	by default, todo asks before removing more notes than the remove_confirm_threshold in the config file
+	todo never asks if the standard input is not a terminal",
+        );
+
+    app.arg(title)
+        .arg(index)
+        .arg(id)
+        .arg(lvl)
+        .arg(tag)
+        .arg(arg_query("remove the notes matching a query"))
+        .arg(dry_run)
+        .arg(yes)
+}
+
+pub fn app_edit() -> App<'static> {
+    let app = App::new("edit")
+        .visible_alias("e")
+        .about("edit notes")
+        .setting(AppSettings::ArgRequiredElseHelp);
+
+    let title = Arg::new("title")
+        .about("a glob pattern matching the title of the note to edit")
+        .long_about(
+            "a glob pattern matching the title of the note to edit. matching is case insensitive",
+        )
+        .multiple(true);
+
+    let index = Arg::new("index")
+        .short('i')
+        .long("index")
+        .about("index of the note to edit")
+        .long_about(
+            "index of the note to edit
+	syntax: START:END
+	or N
+	START or END can be omitted
+	negative numbers are allowed (will count from the end of the list)
+	the newest note will be index 0",
+        )
+        .takes_value(true)
+        .validator(validate_index);
+
+    let id = Arg::new("id")
+        .long("id")
+        .takes_value(true)
+        .about("comma separated list of ids of the notes to edit")
+        .long_about(
+            "comma separated list of ids of the notes to edit
+	an id can be shortened to any prefix that matches only one note",
+        )
+        .validator(validate_id)
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let lvl = Arg::new("lvl")
+        .short('l')
+        .long("level")
+        .takes_value(true)
+        .about("edit notes matching the importance level")
+        .long_about(
+            "edit notes matching the importance level
+	syntax: MIN:MAX or LVL
+	MIN or MAX can be omitted",
+        )
+        .validator(validate_minmax);
+
+    let tag = Arg::new("tag")
+        .short('t')
+        .long("tag")
+        .takes_value(true)
+        .about("comma separated list of tags to select notes by")
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let all = Arg::new("all")
+        .short('a')
+        .long("all")
+        .about("edit every selected note")
+        .long_about(
+            "edit every selected note
+	without this flag, todo refuses to edit more than one note",
+        );
+
+    let set_title = Arg::new("set-title")
+        .long("set-title")
+        .takes_value(true)
+        .about("the new title");
+
+    let set_body = Arg::new("set-body")
+        .long("set-body")
+        .takes_value(true)
+        .about("the new body");
+
+    let add_tag = Arg::new("add-tag")
+        .long("add-tag")
+        .takes_value(true)
+        .about("comma separated list of tags to add")
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let remove_tag = Arg::new("remove-tag")
+        .long("remove-tag")
+        .takes_value(true)
+        .about("comma separated list of tags to remove")
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let set_lvl = Arg::new("set-lvl")
+        .long("set-lvl")
+        .takes_value(true)
+        .about("the new importance level (0..255)")
+        .validator(|s: &str| -> Result<(), String> {
+            s.parse::<u8>()
+                .map_err(|_| {
+                    format!(
+                        "invalid importance level '{}': must be a number between 0 and 255",
+                        s
+                    )
+                })
+                .map(|_| ())
+        });
+
+    let clear_lvl = Arg::new("clear-lvl")
+        .long("clear-lvl")
+        .about("remove the importance level")
+        .conflicts_with("set-lvl");
+
+    let set_due = Arg::new("set-due")
+        .long("set-due")
+        .takes_value(true)
+        .about("the new due date")
+        .long_about(
+            "the new due date
+	accepted values:
+	a date: YYYY-MM-DD or YYYY-MM-DD HH:MM
+	today, tomorrow or a weekday such as fri (the next one after today)
+	a duration from now such as +3d, +12h or +2w
+	a date without a time means the end of that day",
+        )
+        .validator(validate_due);
+
+    let clear_due = Arg::new("clear-due")
+        .long("clear-due")
+        .about("remove the due date")
+        .conflicts_with("set-due");
+
+    let editor = Arg::new("editor")
+        .short('e')
+        .long("editor")
+        .about("edit the note in your editor")
+        .long_about(
+            "edit the note in your editor
+	this is the default if no other change is specified
+	the editor is taken from the $VISUAL or the $EDITOR env variable",
+        );
+
+    app.arg(title)
+        .arg(index)
+        .arg(id)
+        .arg(lvl)
+        .arg(tag)
+        .arg(arg_query("edit the notes matching a query"))
+        .arg(all)
+        .arg(set_title)
+        .arg(set_body)
+        .arg(add_tag)
+        .arg(remove_tag)
+        .arg(set_lvl)
+        .arg(clear_lvl)
+        .arg(set_due)
+        .arg(clear_due)
+        .arg(editor)
+}
+
+fn app_mark(name: &'static str) -> App<'static> {
+    let app = App::new(name).setting(AppSettings::ArgRequiredElseHelp);
+
+    let title = Arg::new("title")
+        .about("a glob pattern matching the note title")
+        .long_about("a glob pattern matching the note title. matching is case insensitive")
+        .multiple(true);
+
+    let index = Arg::new("index")
+        .short('i')
+        .long("index")
+        .about("index of the note")
+        .long_about(
+            "index of the note
+	syntax: START:END
+	or N
+	START or END can be omitted
+	negative numbers are allowed (will count from the end of the list)
+	the newest note will be index 0",
+        )
+        .takes_value(true)
+        .validator(validate_index);
+
+    let id = Arg::new("id")
+        .long("id")
+        .takes_value(true)
+        .about("comma separated list of note ids")
+        .long_about(
+            "comma separated list of note ids
+	an id can be shortened to any prefix that matches only one note",
+        )
+        .validator(validate_id)
+        .setting(ArgSettings::UseValueDelimiter);
+
+    let lvl = Arg::new("lvl")
+        .short('l')
+        .long("level")
+        .takes_value(true)
+        .about("select notes matching the importance level")
+        .long_about(
+            "select notes matching the importance level
+	syntax: MIN:MAX or LVL
+	MIN or MAX can be omitted",
+        )
+        .validator(validate_minmax);
+
+    let tag = Arg::new("tag")
+        .short('t')
+        .long("tag")
+        .takes_value(true)
+        .about("comma separated list of tags to select notes by")
+        .setting(ArgSettings::UseValueDelimiter);
+
+    app.arg(title)
+        .arg(index)
+        .arg(id)
+        .arg(lvl)
+        .arg(tag)
+        .arg(arg_query("select the notes matching a query"))
+}
+
+pub fn app_done() -> App<'static> {
+    app_mark("done")
+        .visible_alias("d")
+        .about("mark notes as done")
+}
+
+pub fn app_reopen() -> App<'static> {
+    app_mark("reopen")
+        .visible_alias("undone")
+        .about("mark notes as not done")
+}
+
+fn arg_format() -> Arg<'static> {
+    Arg::new("format")
+        .short('f')
+        .long("format")
+        .takes_value(true)
+        .about("the output format")
+        .long_about(
+            "the output format
+	human is meant for reading, the rest are meant for scripts
+	the machine readable formats are described in the readme",
+        )
+        .possible_values(&["human", "json", "jsonl", "toml", "csv", "tsv"])
+        .default_value("human")
+}
+
+fn arg_color() -> Arg<'static> {
+    Arg::new("color")
+        .long("color")
+        .takes_value(true)
+        .about("when to color the output")
+        .long_about(
+            "when to color the output
+	auto colors the output only if it's a terminal and the NO_COLOR env variable is not set
+	the colors are configured in the theme section of the config file",
+        )
+        .possible_values(&["auto", "always", "never"])
+        .default_value("auto")
+}
+
+fn arg_template() -> Arg<'static> {
+    Arg::new("template")
+        .long("template")
+        .takes_value(true)
+        .about(
+            "print the notes with a template, such as '{index:>3} {title}{?tags} [{tags}]{/tags}'",
+        )
+        .long_about(
+            "print the notes with a template, such as '{index:>3} {title}{?tags} [{tags}]{/tags}'
+	overrides the list_template in the config file
+	fields: index, id, title, body, tags, lvl, due, done, created, updated, status
+	{field:20} pads the field to 20 characters, {field:>20} aligns it to the right
+	{field:.20} truncates the field to 20 characters
+	{?field}...{/field} is printed only if the field is not empty, {!field}...{/field} only if it is
+	{{ and }} are literal braces, \\n is a newline and \\t is a tab",
+        )
+        .conflicts_with("format")
+}
+
+/// Returns the `regex`, `fuzzy` and `case-sensitive` flags, which require the `requires` argument if it's given.
+fn args_search_mode(requires: Option<&'static str>) -> Vec<Arg<'static>> {
+    let regex = Arg::new("regex")
+        .long("regex")
+        .about("treat the search as a regular expression")
+        .conflicts_with("fuzzy");
+
+    let fuzzy = Arg::new("fuzzy")
+        .long("fuzzy")
+        .about("match the characters of the search in order, with anything in between them");
+
+    let case_sensitive = Arg::new("case-sensitive")
+        .long("case-sensitive")
+        .about("match the case of the search");
+
+    let args = vec![regex, fuzzy, case_sensitive];
+    match requires {
+        Some(name) => args.into_iter().map(|a| a.requires(name)).collect(),
+        None => args,
+    }
+}
+
+fn arg_query(about: &'static str) -> Arg<'static> {
+    Arg::new("query")
+        .short('q')
+        .long("query")
+        .takes_value(true)
+        .about(about)
+        .long_about(
+            "a query such as 'tag:work and not (is:done or lvl<3)'
+	terms are combined with and, or, not and parentheses; terms next to each other are combined with and
+	tag:GLOB, title:GLOB, body:GLOB: case insensitive glob patterns
+	tag~REGEX, title~REGEX, body~REGEX: case insensitive regular expressions
+	id:PREFIX: the id starts with PREFIX
+	lvl=N, lvl!=N, lvl<N, lvl<=N, lvl>N, lvl>=N: importance level comparisons
+	created, updated, done, due: date comparisons such as created>=3d or due<=+1w
+	is:done, is:pending, is:overdue: the state of the note
+	has:due, has:lvl, has:tags, has:body: the note has the field
+	WORD or \"some text\": the title, body or tags contain the text
+	quote values that contain spaces or parentheses",
+        )
+}
+
+fn arg_count(about: &'static str, default: &'static str) -> Arg<'static> {
+    Arg::new("n")
+        .about(about)
+        .default_value(default)
+        .validator(|s| match s.parse::<usize>() {
+            Err(_) | Ok(0) => Err(format!(
+                "{}: the value must be an integer greater than 0",
+                s
+            )),
+            Ok(_) => Ok(()),
+        })
+}
+
+pub fn app_undo() -> App<'static> {
+    App::new("undo")
+        .about("undo the last changes made to the notes")
+        .long_about(
+            "undo the last changes made to the notes
+	every command that modifies the notes is recorded in a journal next to the todos file
+	run `todo history` to see what will be undone",
+        )
+        .arg(arg_count("the number of changes to undo", "1"))
+}
+
+pub fn app_redo() -> App<'static> {
+    App::new("redo")
+        .about("redo the last undone changes")
+        .long_about(
+            "redo the last undone changes
+	undone changes can't be redone after a new change is made",
+        )
+        .arg(arg_count("the number of changes to redo", "1"))
+}
+
+pub fn app_history() -> App<'static> {
+    App::new("history")
+        .visible_alias("hist")
+        .about("display the recent changes made to the notes, the most recent first")
+        .arg(arg_count("the number of changes to display", "10"))
 }
 
 pub fn app_new() -> App<'static> {
@@ -183,15 +794,22 @@ pub fn app_new() -> App<'static> {
         .visible_alias("n")
         .alias("add");
 
-    let title = Arg::new("title")
-        .about("note title")
-        .takes_value(false)
-        .required(true);
+    let title = Arg::new("title").about("note title").takes_value(false);
 
     let body = Arg::new("body")
         .about("note body")
-        .takes_value(false)
-        .required(true);
+        .long_about("note body. if omitted, the note is composed in your editor")
+        .takes_value(false);
+
+    let editor = Arg::new("editor")
+        .short('e')
+        .long("editor")
+        .about("compose the note in your editor")
+        .long_about(
+            "compose the note in your editor
+	the editor is taken from the $VISUAL or the $EDITOR env variable
+	the title, tags and the level given on the command line are filled in",
+        );
 
     let tag = Arg::new("tag")
         .about("comma separated list of tags")
@@ -218,7 +836,27 @@ pub fn app_new() -> App<'static> {
                 .map(|_| ())
         });
 
-    app.arg(title).arg(body).arg(tag).arg(lvl)
+    let due = Arg::new("due")
+        .short('d')
+        .long("due")
+        .takes_value(true)
+        .about("due date of the note")
+        .long_about(
+            "due date of the note
+	accepted values:
+	a date: YYYY-MM-DD or YYYY-MM-DD HH:MM
+	today, tomorrow or a weekday such as fri (the next one after today)
+	a duration from now such as +3d, +12h or +2w
+	a date without a time means the end of that day",
+        )
+        .validator(validate_due);
+
+    app.arg(title)
+        .arg(body)
+        .arg(tag)
+        .arg(lvl)
+        .arg(due)
+        .arg(editor)
 }
 
 pub fn app_where() -> App<'static> {
@@ -237,6 +875,7 @@ pub fn app_where() -> App<'static> {
                 .about("show todos file path")
                 .long("todos"),
         )
+        .arg(arg_format())
 }
 
 pub fn app_random() -> App<'static> {
@@ -262,5 +901,127 @@ pub fn app_random() -> App<'static> {
         .takes_value(true)
         .setting(ArgSettings::UseValueDelimiter);
 
-    app.arg(n).arg(tags)
+    app.arg(n)
+        .arg(tags)
+        .arg(arg_format())
+        .arg(arg_template())
+        .arg(arg_color())
+}
+
+pub fn app_search() -> App<'static> {
+    let app = App::new("search")
+        .visible_alias("s")
+        .about("search the titles, bodies and tags of the notes, the best matches first")
+        .long_about(
+            "search the titles, bodies and tags of the notes, the best matches first
+	the search is case insensitive unless --case-sensitive is given
+	matches in the title rank higher than the ones in the tags, which rank higher than the ones in the body",
+        );
+
+    let pattern = Arg::new("pattern")
+        .about("the text to search for")
+        .required(true);
+
+    let done = Arg::new("done")
+        .short('d')
+        .long("done")
+        .about("search only the completed notes")
+        .conflicts_with("all");
+
+    let all = Arg::new("all")
+        .short('a')
+        .long("all")
+        .about("search completed notes as well");
+
+    let time = Arg::new("time")
+        .short('T')
+        .long("time")
+        .about("display the creation and modification times of the notes");
+
+    app.arg(pattern)
+        .args(args_search_mode(None))
+        .arg(done)
+        .arg(all)
+        .arg(time)
+        .arg(arg_format())
+        .arg(arg_template())
+        .arg(arg_color())
+}
+
+pub fn app_view(views: &[&'static str]) -> App<'static> {
+    let app = App::new("view")
+        .visible_alias("v")
+        .about("display the notes of a view defined in the config file, or list the views")
+        .long_about(
+            "display the notes of a view defined in the config file, or list the views if no name is given
+	a view is a named set of list arguments, it can also be displayed with `todo list @NAME`
+	arguments after the name are passed to list and override the ones of the view
+	titles are the exception, they're added to the titles of the view",
+        )
+        .setting(AppSettings::TrailingVarArg)
+        .setting(AppSettings::AllowLeadingHyphen);
+
+    let mut name = Arg::new("name").about("the name of the view");
+    if !views.is_empty() {
+        name = name.possible_values(views);
+    }
+
+    let args = Arg::new("args")
+        .about("more list arguments")
+        .multiple(true)
+        .setting(ArgSettings::AllowHyphenValues);
+
+    app.arg(name).arg(args)
+}
+
+pub fn app_completions() -> App<'static> {
+    App::new("completions")
+        .about("print a shell completion script")
+        .long_about(
+            "print a shell completion script
+	unlike the scripts generated at build time, it completes the names of the views in the config file
+	the view names are completed in bash, fish and zsh",
+        )
+        .arg(
+            Arg::new("shell")
+                .about("the shell to print the script for")
+                .required(true)
+                .possible_values(&["bash", "elvish", "fish", "powershell", "zsh"]),
+        )
+}
+
+pub fn app_lists() -> App<'static> {
+    App::new("lists").about(
+        "display the todo lists in the config file, with the number of notes in them; the one in use is marked with *",
+    )
+}
+
+pub fn app_move() -> App<'static> {
+    let to = Arg::new("to")
+        .long("to")
+        .takes_value(true)
+        .required(true)
+        .about("the name of the list to move the notes to");
+
+    app_mark("move")
+        .visible_alias("mv")
+        .about("move notes to another todo list")
+        .arg(to)
+}
+
+pub fn app_git() -> App<'static> {
+    App::new("git")
+        .about("run git in the directory of the todos file")
+        .long_about(
+            "run git in the directory of the todos file, such as `todo git log` or `todo git init`
+	the changes are committed automatically if auto_commit is set in the git section of the config file",
+        )
+        .setting(AppSettings::TrailingVarArg)
+        .setting(AppSettings::AllowLeadingHyphen)
+        .arg(
+            Arg::new("args")
+                .about("the arguments to pass to git")
+                .multiple(true)
+                .setting(ArgSettings::AllowHyphenValues),
+        )
 }
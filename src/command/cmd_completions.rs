@@ -0,0 +1,142 @@
+use super::{alias, app};
+
+use crate::config::Config;
+
+use clap::ArgMatches;
+use clap_generate::{
+    generate,
+    generators::{Bash, Elvish, Fish, PowerShell, Zsh},
+};
+
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+#[derive(Debug)]
+pub struct CompletionsCommand {
+    pub shell: String,
+}
+
+impl CompletionsCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        Self {
+            shell: m
+                .value_of("shell")
+                .expect("internal error: missing required argument shell")
+                .to_owned(),
+        }
+    }
+
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        // the app borrows its strings for 'static; the process ends right after printing anyway
+        let views: Vec<&'static str> = c
+            .views
+            .keys()
+            .cloned()
+            .map(|s| &*Box::leak(s.into_boxed_str()))
+            .collect();
+        let builtins = alias::builtins(&app::app());
+        let mut app = app::app_with(&views, &alias::subcommands(&c.aliases, &builtins));
+        let out = &mut io::stdout();
+        match &self.shell[..] {
+            "bash" => {
+                generate::<Bash, _>(&mut app, "todo", out);
+                if !views.is_empty() {
+                    out.write_all(bash_views(&views).as_bytes())?;
+                }
+            }
+            "elvish" => {
+                let mut script = Vec::new();
+                generate::<Elvish, _>(&mut app, "todo", &mut script);
+                let script = add_views(script, "&'todo;view'= {\n", &views, |v| {
+                    format!("            cand {} 'view'\n", quote(v))
+                })?;
+                out.write_all(script.as_bytes())?;
+            }
+            "fish" => generate::<Fish, _>(&mut app, "todo", out),
+            "powershell" => {
+                let mut script = Vec::new();
+                generate::<PowerShell, _>(&mut app, "todo", &mut script);
+                let script = add_views(script, "'todo;view' {\n", &views, |v| {
+                    format!(
+                        "            [CompletionResult]::new({0}, {0}, [CompletionResultType]::ParameterValue, 'view')\n",
+                        quote(v)
+                    )
+                })?;
+                out.write_all(script.as_bytes())?;
+            }
+            "zsh" => generate::<Zsh, _>(&mut app, "todo", out),
+            _ => panic!("internal error: unknown shell {}", self.shell),
+        }
+        Ok(())
+    }
+}
+
+/// Returns the bash script that completes the view names after `todo view`,
+/// which the generated script doesn't do, and leaves the rest to the generated `_todo`.
+fn bash_views(views: &[&str]) -> String {
+    let words = views.join(" ").replace('\'', "'\\''");
+    format!(
+        r#"
+_todo_views() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ ${{COMP_CWORD}} -ge 2 && "${{COMP_WORDS[COMP_CWORD-1]}}" == view && "${{cur}}" != -* ]]; then
+        COMPREPLY=( $(compgen -W '{}' -- "${{cur}}") )
+        return 0
+    fi
+    _todo "$@"
+}}
+
+complete -F _todo_views -o bashdefault -o default todo
+"#,
+        words
+    )
+}
+
+/// Inserts a completion line for every view, made by `line`, right after `marker`,
+/// the start of the completions of the `view` command in the generated `script`.
+fn add_views(
+    script: Vec<u8>,
+    marker: &str,
+    views: &[&str],
+    line: impl Fn(&str) -> String,
+) -> Result<String, Box<dyn Error>> {
+    let mut script = String::from_utf8(script)?;
+    let at = script
+        .find(marker)
+        .ok_or("internal error: no completions of the view command in the generated script")?
+        + marker.len();
+    let lines: String = views.iter().map(|v| line(v)).collect();
+    script.insert_str(at, &lines);
+    Ok(script)
+}
+
+/// Quotes `s` for elvish and PowerShell, where a single quote is escaped by doubling it.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_are_added_to_the_view_command() {
+        let script = b"&'todo;view'= {\n    cand -h 'help'\n}\n".to_vec();
+        let script = add_views(script, "&'todo;view'= {\n", &["it's", "urgent"], |v| {
+            format!("    cand {} 'view'\n", quote(v))
+        })
+        .unwrap();
+        assert_eq!(
+            script,
+            "&'todo;view'= {\n    cand 'it''s' 'view'\n    cand 'urgent' 'view'\n    cand -h 'help'\n}\n"
+        );
+        assert!(
+            add_views(Vec::new(), "&'todo;view'= {\n", &["urgent"], |v| v
+                .to_owned())
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use super::query::Selector;
+
+use crate::{
+    config::{
+        hook::{Context, Rewrite},
+        Config,
+    },
+    journal::{self, Entry, Snapshot},
+    note::{self, Notes},
+};
+
+use clap::ArgMatches;
+
+use std::error::Error;
+
+/// Marks notes as done, or as not done if `done` is false.
+#[derive(Debug)]
+pub struct DoneCommand {
+    pub selector: Selector,
+    pub done: bool,
+}
+
+impl DoneCommand {
+    pub fn from_matches(m: &ArgMatches, done: bool) -> Self {
+        Self {
+            selector: Selector::from_matches(m),
+            done,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let command = if self.done { "done" } else { "reopen" };
+
+        // the pre hooks run without holding the lock, the notes are loaded again afterwards
+        let notes = note::get_notes(&c.todos_file)?;
+        if notes.is_empty() {
+            println!("you have no todos");
+            return Ok(());
+        }
+        let selected: Vec<_> = self
+            .selector
+            .select(&notes)?
+            .into_iter()
+            .filter(|&i| notes[i].is_done() != self.done)
+            .collect();
+        if selected.is_empty() {
+            println!("no match, nothing to do");
+            return Ok(());
+        }
+        let mut ctx = Context::new(command, &c.todos_file, &c.list)
+            .with_notes(selected.iter().map(|&i| (i, &notes[i])))
+            .with_rewrite(Rewrite::Select);
+        c.run_pre_hooks(&mut ctx)?;
+        // the pre hooks may have left some of them out
+        let ids: Vec<_> = ctx.notes.into_iter().map(|(_, n)| n.id).collect();
+        if ids.is_empty() {
+            println!("the hooks left no note to change, nothing to do");
+            return Ok(());
+        }
+
+        let lock = c.lock()?;
+        let mut notes = note::get_notes(&c.todos_file)?;
+        let selected: Vec<_> = (0..notes.len())
+            .filter(|&i| ids.contains(&notes[i].id) && notes[i].is_done() != self.done)
+            .collect();
+        if selected.is_empty() {
+            println!("the notes were changed by another process, nothing to do");
+            return Ok(());
+        }
+
+        let now = note::now();
+        let mut before = Vec::with_capacity(selected.len());
+        let mut after = Vec::with_capacity(selected.len());
+        for &i in &selected {
+            before.push(Snapshot::new(i, &notes[i]));
+            notes[i].done = if self.done { Some(now) } else { None };
+            notes[i].updated = Some(now);
+            after.push(Snapshot::new(i, &notes[i]));
+        }
+        let entry = Entry::new(
+            command,
+            journal::summary(command, after.iter().map(|s| &s.note)),
+            before,
+            after,
+        );
+        let notes = Notes::new(notes);
+        c.save_change(&notes, entry)?;
+        drop(lock);
+
+        let status = if self.done { "done" } else { "not done" };
+        let notes = notes.todo.as_deref().unwrap_or_default();
+        if selected.len() == 1 {
+            println!("marked 1 note as {}:", status);
+        } else {
+            println!("marked {} notes as {}:", selected.len(), status);
+        }
+        for &i in &selected {
+            println!("-  {}  {}", notes[i].id, notes[i].title);
+        }
+
+        let ctx = Context::new(command, &c.todos_file, &c.list)
+            .with_notes(selected.iter().map(|&i| (i, &notes[i])));
+        c.hooks.run_on_change(&ctx)?;
+        c.hooks.run_post(&ctx)?;
+        Ok(())
+    }
+}
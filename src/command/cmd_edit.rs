@@ -0,0 +1,230 @@
+use super::query::Selector;
+
+use crate::{
+    config::{
+        hook::{Context, Rewrite},
+        Config,
+    },
+    date, editor,
+    journal::{self, Entry, Snapshot},
+    note::{self, Note, Notes},
+};
+
+use chrono::{DateTime, Local};
+use clap::ArgMatches;
+
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct EditCommand {
+    pub selector: Selector,
+    pub all: bool,
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub lvl: Option<Option<u8>>,
+    pub due: Option<Option<DateTime<Local>>>,
+    pub add_tags: Option<Vec<String>>,
+    pub remove_tags: Option<Vec<String>>,
+    pub editor: bool,
+}
+
+/// Applies the fields `edited` changed from `original` to `current`, the note as it's in the file now,
+/// so the changes other processes made to it since `original` was read aren't reverted.
+fn merge(original: &Note, edited: &Note, current: &mut Note) {
+    if edited.title != original.title {
+        current.title = edited.title.clone();
+    }
+    if edited.body != original.body {
+        current.body = edited.body.clone();
+    }
+    if edited.tags != original.tags {
+        current.tags = edited.tags.clone();
+    }
+    if edited.lvl != original.lvl {
+        current.lvl = edited.lvl;
+    }
+    if edited.due != original.due {
+        current.due = edited.due;
+    }
+    current.updated = edited.updated;
+}
+
+impl EditCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        let title = m.value_of("set-title").map(String::from);
+        let body = m.value_of("set-body").map(String::from);
+        let lvl = if m.is_present("clear-lvl") {
+            Some(None)
+        } else {
+            m.value_of("set-lvl")
+                .map(|s| Some(s.parse::<u8>().unwrap()))
+        };
+        let due = if m.is_present("clear-due") {
+            Some(None)
+        } else {
+            m.value_of("set-due").map(|s| {
+                Some(
+                    date::parse_due(s, note::now())
+                        .expect("internal error: date::parse_due returned None"),
+                )
+            })
+        };
+        let add_tags = m
+            .values_of("add-tag")
+            .map(|i| i.map(String::from).collect::<Vec<_>>());
+        let remove_tags = m
+            .values_of("remove-tag")
+            .map(|i| i.map(String::from).collect::<Vec<_>>());
+
+        Self {
+            selector: Selector::from_matches(m),
+            all: m.is_present("all"),
+            title,
+            body,
+            lvl,
+            due,
+            add_tags,
+            remove_tags,
+            editor: m.is_present("editor"),
+        }
+    }
+
+    fn has_changes(&self) -> bool {
+        self.title.is_some()
+            || self.body.is_some()
+            || self.lvl.is_some()
+            || self.due.is_some()
+            || self.add_tags.is_some()
+            || self.remove_tags.is_some()
+    }
+
+    fn apply(&self, n: &mut Note) {
+        if let Some(title) = &self.title {
+            n.title = title.clone();
+        }
+        if let Some(body) = &self.body {
+            n.body = body.clone();
+        }
+        if let Some(lvl) = self.lvl {
+            n.lvl = lvl;
+        }
+        if let Some(due) = self.due {
+            n.due = due;
+        }
+
+        let mut tags = n.tags.take().unwrap_or_default();
+        if let Some(add) = &self.add_tags {
+            for t in add {
+                if !tags.contains(t) {
+                    tags.push(t.clone());
+                }
+            }
+        }
+        if let Some(remove) = &self.remove_tags {
+            tags.retain(|t| !remove.contains(t));
+        }
+        if !tags.is_empty() {
+            n.tags = Some(tags);
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.selector.is_empty() {
+            return Err(
+                "no note specified; select the notes to edit by index, id, title, tag or level"
+                    .into(),
+            );
+        }
+        let use_editor = self.editor || !self.has_changes();
+
+        let c = Config::get()?;
+        // the notes are read without locking first, so that the lock isn't held while the user is in the editor
+        let mut notes = note::get_notes(&c.todos_file)?;
+
+        if notes.is_empty() {
+            println!("you have no todos");
+            return Ok(());
+        }
+
+        let selected = self.selector.select(&notes)?;
+        if selected.is_empty() {
+            println!("no match, nothing to do");
+            return Ok(());
+        }
+        if selected.len() > 1 && !self.all {
+            println!("the selection matches {} notes:", selected.len());
+            for &i in &selected {
+                println!("-  {}  {}", notes[i].id, notes[i].title);
+            }
+            return Err("refusing to edit more than 1 note, use --all to edit all of them".into());
+        }
+
+        let originals: Vec<_> = selected.iter().map(|&i| notes[i].clone()).collect();
+        let mut edited = Vec::with_capacity(selected.len());
+        for &i in &selected {
+            self.apply(&mut notes[i]);
+            if use_editor {
+                notes[i] = match editor::compose(&notes[i])? {
+                    Some(n) => n,
+                    None => {
+                        println!("the note is empty, aborting");
+                        return Ok(());
+                    }
+                };
+            }
+            notes[i].touch();
+            edited.push(notes[i].clone());
+        }
+
+        let mut ctx = Context::new("edit", &c.todos_file, &c.list)
+            .with_notes(selected.iter().copied().zip(&edited))
+            .with_rewrite(Rewrite::Fields);
+        c.run_pre_hooks(&mut ctx)?;
+        // the pre hooks may have rewritten them
+        let edited: Vec<_> = ctx.notes.into_iter().map(|(_, n)| n).collect();
+
+        let lock = c.lock()?;
+        let mut notes = note::get_notes(&c.todos_file)?;
+        let mut before = Vec::with_capacity(edited.len());
+        let mut after = Vec::with_capacity(edited.len());
+        let mut merged = Vec::with_capacity(edited.len());
+        for (original, n) in originals.iter().zip(&edited) {
+            match notes.iter().position(|x| x.id == n.id) {
+                Some(i) => {
+                    before.push(Snapshot::new(i, &notes[i]));
+                    merge(original, n, &mut notes[i]);
+                    after.push(Snapshot::new(i, &notes[i]));
+                    merged.push(notes[i].clone());
+                }
+                None => {
+                    return Err(format!(
+                        "the note {} was removed by another process, aborting",
+                        n.id
+                    )
+                    .into())
+                }
+            };
+        }
+        let edited = merged;
+        let indices: Vec<_> = after.iter().map(|s| s.index).collect();
+        let entry = Entry::new("edit", journal::summary("edit", &edited), before, after);
+        let notes = Notes::new(notes);
+        c.save_change(&notes, entry)?;
+        drop(lock);
+
+        if edited.len() == 1 {
+            println!("edited 1 note:");
+        } else {
+            println!("edited {} notes:", edited.len());
+        }
+        for n in &edited {
+            println!("-  {}  {}", n.id, n.title);
+        }
+
+        let ctx = Context::new("edit", &c.todos_file, &c.list)
+            .with_notes(indices.into_iter().zip(&edited));
+        c.hooks.run_on_change(&ctx)?;
+        c.hooks.run_post(&ctx)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::config::{git, Config};
+
+use clap::ArgMatches;
+
+use std::{error::Error, io, process::Command};
+
+/// Runs git in the directory of the todos file.
+#[derive(Debug)]
+pub struct GitCommand {
+    pub args: Vec<String>,
+}
+
+impl GitCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        Self {
+            args: m
+                .values_of("args")
+                .map(|i| i.map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let status = Command::new("git")
+            .args(&self.args)
+            .current_dir(git::dir_of(&c.todos_file))
+            .status()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => "git is not installed, or not in the PATH".to_owned(),
+                _ => format!("could not run git: {}", e),
+            })?;
+        if status.success() {
+            Ok(())
+        } else {
+            // git has already explained what went wrong
+            Err(format!("git exited with {}", status).into())
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{config::Config, journal::Journal};
+
+use clap::ArgMatches;
+
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct HistoryCommand {
+    pub n: usize,
+}
+
+impl HistoryCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        let n = m.value_of("n").unwrap().parse::<usize>().unwrap();
+        Self { n }
+    }
+
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let journal = Journal::load(c.journal_path())?;
+
+        if journal.entry.is_empty() {
+            println!("no history");
+            return Ok(());
+        }
+
+        // the most recent operation is the first one and the next one to be undone
+        for e in journal.entry.iter().rev().take(self.n) {
+            println!(
+                "{time}  {summary}{undone}",
+                time = e.time.format("%Y-%m-%d %H:%M:%S"),
+                summary = e.summary,
+                undone = if e.undone { "  (undone)" } else { "" },
+            );
+        }
+        Ok(())
+    }
+}
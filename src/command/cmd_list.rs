@@ -1,9 +1,18 @@
 use super::{
     index::{Index, MinMax},
-    query::Filter,
+    query::{self, Filter},
+    sort::{self, SortKey},
 };
 
-use crate::{config::Config, note};
+use crate::{
+    config::Config,
+    date,
+    format::{self, Format},
+    note,
+    render::{ColorChoice, Renderer},
+    search::Search,
+    template,
+};
 
 use clap::ArgMatches;
 use glob::Pattern;
@@ -13,14 +22,35 @@ use std::error::Error;
 #[derive(Debug)]
 pub struct ListCommand {
     pub index: Index,
+    /// How many of the matching notes to show at most, the most recent ones.
+    pub limit: Option<usize>,
     pub filter: Filter,
+    pub sort: Vec<SortKey>,
+    pub show_time: bool,
+    pub format: Format,
+    pub template: Option<String>,
+    pub color: ColorChoice,
+    pub search: Option<Search>,
+    /// The `--query` expression, parsed into the filter when running.
+    pub query: Option<String>,
 }
 
 impl Default for ListCommand {
     fn default() -> Self {
         Self {
-            index: Index::Between((0, 5)),
-            filter: Filter::default(),
+            index: Index::default(),
+            limit: Some(5),
+            filter: Filter {
+                done: Some(false),
+                ..Filter::default()
+            },
+            sort: Vec::new(),
+            show_time: false,
+            format: Format::Human,
+            template: None,
+            color: ColorChoice::Auto,
+            search: None,
+            query: None,
         }
     }
 }
@@ -41,23 +71,91 @@ impl ListCommand {
             .map(|s| Index::parse(s).expect("internal error: Index::parse returned None"))
             .unwrap_or_default();
 
+        let ids = m.values_of("id").map(|i| i.map(String::from).collect());
         let tags = m.values_of("tag").map(|i| i.map(String::from).collect());
         let lvl = m
             .value_of("lvl")
             .map(|s| MinMax::parse(s).expect("internal error: MinMax::parse returned None"));
 
+        let done = if m.is_present("all") {
+            None
+        } else {
+            Some(m.is_present("done"))
+        };
+
+        let now = note::now();
+        let since = m.value_of("since").map(|s| {
+            date::parse_past(s, now).expect("internal error: date::parse_past returned None")
+        });
+        let before = m.value_of("before").map(|s| {
+            date::parse_past(s, now).expect("internal error: date::parse_past returned None")
+        });
+        let due = if m.is_present("overdue") {
+            Some(now)
+        } else {
+            m.value_of("due-within").map(|s| {
+                let d = date::parse_duration(s)
+                    .expect("internal error: date::parse_duration returned None");
+                now + d
+            })
+        };
+        let sort = m
+            .values_of("sort")
+            .map(|i| {
+                i.map(|s| SortKey::parse(s).expect("internal error: SortKey::parse returned None"))
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Self {
             index,
-            filter: Filter { titles, lvl, tags },
+            limit: None,
+            filter: Filter {
+                ids,
+                titles,
+                lvl,
+                tags,
+                done,
+                since,
+                before,
+                due,
+                search: None,
+                query: None,
+            },
+            sort,
+            show_time: m.is_present("time"),
+            format: m
+                .value_of("format")
+                .map(|s| {
+                    s.parse()
+                        .expect("internal error: Format::from_str returned an error")
+                })
+                .unwrap_or_default(),
+            template: m.value_of("template").map(String::from),
+            color: m
+                .value_of("color")
+                .map(|s| {
+                    s.parse()
+                        .expect("internal error: ColorChoice::from_str returned an error")
+                })
+                .unwrap_or_default(),
+            search: query::search_from_matches(m, "search"),
+            query: m.value_of("query").map(String::from),
         }
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         let c = Config::get()?;
-        let notes: Vec<_> = note::get_notes(&c.todos_file)?
-            .into_iter()
-            .enumerate()
-            .collect();
+        let template = template::resolve(self.template.as_deref(), c.list_template.as_deref())?;
+        let mut renderer = Renderer::new(&c.theme, self.color)?;
+        let notes = note::get_notes(&c.todos_file)?;
+        self.filter.resolve_ids(&notes)?;
+        self.filter.search = self.search.as_ref().map(Search::matcher).transpose()?;
+        self.filter.query = self.query.as_deref().map(str::parse).transpose()?;
+        if let Some(m) = &self.filter.search {
+            renderer = renderer.with_search(m);
+        }
+        let notes: Vec<_> = notes.into_iter().enumerate().collect();
         // self.index.calibrate(notes.len());
 
         let mut filtered: Vec<_> = self
@@ -67,12 +165,24 @@ impl ListCommand {
             .iter()
             .filter(|(_, n)| self.filter.is_match(n))
             .collect();
+        // after filtering, so the notes that don't match don't take the place of older ones that do
+        if let Some(n) = self.limit {
+            filtered.truncate(n);
+        }
 
         if self.index.is_reversed() {
             filtered = filtered.into_iter().rev().collect();
         }
+        if !self.sort.is_empty() {
+            sort::sort_printed(&self.sort, &mut filtered);
+        }
 
-        note::print_notes_enumerated(&filtered[..]);
-        Ok(())
+        format::print_notes(
+            self.format,
+            template.as_ref(),
+            &renderer,
+            &filtered[..],
+            self.show_time,
+        )
     }
 }
@@ -0,0 +1,36 @@
+use crate::{config::Config, note};
+
+use std::error::Error;
+
+/// Displays the todo lists with the number of notes in them.
+#[derive(Debug)]
+pub struct ListsCommand;
+
+impl ListsCommand {
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let names = c.list_names();
+        let width = names.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+        for name in names {
+            let p = c.list_path(name)?;
+            // the lists not used yet have no file
+            let notes = if p.is_file() {
+                note::get_notes(&p)?
+            } else {
+                Vec::new()
+            };
+            let done = notes.iter().filter(|n| n.is_done()).count();
+            let mark = if name == c.list { '*' } else { ' ' };
+            println!(
+                "{} {:width$}  {:>4} pending  {:>4} done  {}",
+                mark,
+                name,
+                notes.len() - done,
+                done,
+                p.display(),
+                width = width
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use super::query::Selector;
+
+use crate::{
+    config::{
+        hook::{Context, Rewrite},
+        Config,
+    },
+    journal::{self, Entry, Linked, Snapshot},
+    note::{self, Notes},
+};
+
+use clap::ArgMatches;
+
+use std::error::Error;
+
+/// Moves notes from the list in use to another one.
+#[derive(Debug)]
+pub struct MoveCommand {
+    pub selector: Selector,
+    pub to: String,
+}
+
+impl MoveCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        Self {
+            selector: Selector::from_matches(m),
+            to: m
+                .value_of("to")
+                .expect("internal error: missing required argument to")
+                .to_owned(),
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let dst = Config::get_list(Some(&self.to))?;
+        if dst.todos_file == c.todos_file {
+            return Err(format!("the notes are already in the list {}", self.to).into());
+        }
+
+        // the pre hooks run without holding the lock, the notes are loaded again afterwards
+        let notes = note::get_notes(&c.todos_file)?;
+        if notes.is_empty() {
+            println!("you have no todos");
+            return Ok(());
+        }
+        let selected = self.selector.select(&notes)?;
+        if selected.is_empty() {
+            println!("no match, nothing to do");
+            return Ok(());
+        }
+        let mut ctx = Context::new("move", &c.todos_file, &c.list)
+            .with_notes(selected.iter().map(|&i| (i, &notes[i])))
+            .with_rewrite(Rewrite::Select);
+        c.run_pre_hooks(&mut ctx)?;
+        // the pre hooks may have left some of them out
+        let ids: Vec<_> = ctx.notes.into_iter().map(|(_, n)| n.id).collect();
+        if ids.is_empty() {
+            println!("the hooks left no note to move, nothing to do");
+            return Ok(());
+        }
+
+        // both lists are locked in a fixed order, so two opposite moves don't wait for each other forever
+        let locks = Config::lock_all(&[&c, &dst])?;
+        let notes = note::get_notes(&c.todos_file)?;
+        let (moved, remaining): (Vec<_>, Vec<_>) = notes
+            .into_iter()
+            .enumerate()
+            .partition(|(_, n)| ids.contains(&n.id));
+        if moved.is_empty() {
+            println!("the notes were removed by another process, nothing to do");
+            return Ok(());
+        }
+        let dst_original = note::get_notes(&dst.todos_file)?;
+        let mut dst_notes = dst_original.clone();
+        let command = format!("move --to {}", self.to);
+        let summary = journal::summary("move", moved.iter().map(|(_, n)| n));
+
+        // the moved notes keep their order and become the most recent ones in the other list
+        let mut added = Vec::with_capacity(moved.len());
+        for (k, (_, n)) in moved.iter().enumerate() {
+            let mut n = n.clone();
+            // 32 bit ids rarely collide, but the ones in the other list weren't checked when this one was made
+            if dst_notes.iter().any(|d| d.id == n.id) {
+                n.id = note::unique_id(&dst_notes);
+            }
+            added.push(Snapshot::new(k, &n));
+            dst_notes.insert(k, n);
+        }
+
+        // the other list is saved first: if saving this one fails, it's restored so the notes aren't in both lists
+        dst.save_notes(&Notes::new(dst_notes))?;
+        if let Err(e) = c.save_notes(&Notes::new(remaining.into_iter().map(|(_, n)| n).collect())) {
+            return Err(match dst.save_notes(&Notes::new(dst_original)) {
+                Ok(_) => e,
+                Err(e2) => format!(
+                    "{}; restoring {} also failed, the notes are in both lists: {}",
+                    e,
+                    dst.todos_file.display(),
+                    e2
+                )
+                .into(),
+            });
+        }
+        // the move is recorded once, in this list's journal, so undoing it here restores both lists
+        c.record(
+            Entry::new(
+                &command,
+                &summary,
+                moved.iter().map(|(i, n)| Snapshot::new(*i, n)).collect(),
+                Vec::new(),
+            )
+            .with_linked(Linked {
+                list: self.to.clone(),
+                file: dst.todos_file.clone(),
+                before: Vec::new(),
+                after: added,
+            }),
+        )?;
+        dst.commit(&summary)?;
+        drop(locks);
+
+        if moved.len() == 1 {
+            println!("moved 1 note to {}:", self.to);
+        } else {
+            println!("moved {} notes to {}:", moved.len(), self.to);
+        }
+        for (_, n) in &moved {
+            println!("-  {}  {}", n.id, n.title);
+        }
+
+        let ctx = Context::new("move", &c.todos_file, &c.list)
+            .with_notes(moved.iter().map(|(i, n)| (*i, n)));
+        c.hooks.run_on_change(&ctx)?;
+        c.hooks.run_post(&ctx)?;
+        Ok(())
+    }
+}
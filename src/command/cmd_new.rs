@@ -1,62 +1,99 @@
 use crate::{
-    config::Config,
+    config::{
+        hook::{Context, Rewrite},
+        Config,
+    },
+    date, editor,
+    journal::{self, Entry, Snapshot},
     note::{self, Note, Notes},
 };
 
+use chrono::{DateTime, Local};
 use clap::ArgMatches;
 
 use std::error::Error;
 
 #[derive(Debug)]
 pub struct NewCommand {
-    pub title: String,
-    pub body: String,
+    pub title: Option<String>,
+    pub body: Option<String>,
     pub lvl: Option<u8>,
     pub tags: Option<Vec<String>>,
+    pub due: Option<DateTime<Local>>,
+    pub editor: bool,
 }
 
 impl NewCommand {
     pub fn from_matches(m: &ArgMatches) -> Self {
-        let title = m.value_of("title").map(String::from).unwrap();
-        let body = m.value_of("body").map(String::from).unwrap();
+        let title = m.value_of("title").map(String::from);
+        let body = m.value_of("body").map(String::from);
         let tags = m
             .values_of("tag")
             .map(|i| i.map(String::from).collect::<Vec<_>>());
 
         let lvl = m.value_of("lvl").map(|s| s.parse::<u8>().unwrap());
+        let due = m.value_of("due").map(|s| {
+            date::parse_due(s, note::now()).expect("internal error: date::parse_due returned None")
+        });
 
         Self {
             title,
             body,
             lvl,
             tags,
+            due,
+            editor: m.is_present("editor"),
         }
     }
 
     pub fn run(self) -> Result<(), Box<dyn Error>> {
-        let c = Config::get()?;
-        let mut notes = note::get_notes(&c.todos_file)?;
-
-        if let Err(e) = c.hooks.run_pre_new() {
-            match c.abort_on_hook_error {
-                Some(true) | None => return Err(Box::new(e)),
-                Some(false) => println!("pre-new hook error: {:?}", e),
-            };
-        }
-
-        let title = self.title.clone();
-        let n = Note {
-            title: self.title,
-            body: self.body,
+        let mut n = Note {
             lvl: self.lvl,
             tags: self.tags,
+            due: self.due,
+            ..Note::new(
+                self.title.unwrap_or_default(),
+                self.body.clone().unwrap_or_default(),
+            )
         };
+        if self.editor || self.body.is_none() {
+            n = match editor::compose(&n)? {
+                Some(n) => n,
+                None => {
+                    println!("the note is empty, aborting");
+                    return Ok(());
+                }
+            };
+        }
+
+        let c = Config::get()?;
+
+        let mut ctx = Context::new("new", &c.todos_file, &c.list)
+            .with_notes([(0, &n)])
+            .with_rewrite(Rewrite::Fields);
+        c.run_pre_hooks(&mut ctx)?;
+        // the pre hooks may have rewritten it
+        let (_, mut n) = ctx.notes.remove(0);
+
+        let lock = c.lock()?;
+        let mut notes = note::get_notes(&c.todos_file)?;
+        n.id = note::unique_id(&notes);
+        let saved = n.clone();
+        let entry = Entry::new(
+            "new",
+            journal::summary("new", Some(&n)),
+            Vec::new(),
+            vec![Snapshot::new(0, &n)],
+        );
         notes.insert(0, n);
         let notes = Notes::new(notes);
-        notes.save_to(&c.todos_file)?;
-        println!("saved {}", title);
+        c.save_change(&notes, entry)?;
+        drop(lock);
+        println!("saved {} ({})", saved.title, saved.id);
 
-        c.hooks.run_post_new()?;
+        let ctx = Context::new("new", &c.todos_file, &c.list).with_notes([(0, &saved)]);
+        c.hooks.run_on_change(&ctx)?;
+        c.hooks.run_post(&ctx)?;
         Ok(())
     }
 }
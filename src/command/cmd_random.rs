@@ -1,4 +1,10 @@
-use crate::{config::Config, note};
+use crate::{
+    config::Config,
+    format::{self, Format},
+    note,
+    render::{ColorChoice, Renderer},
+    template,
+};
 use clap::ArgMatches;
 use rand::seq::SliceRandom;
 use std::error::Error;
@@ -7,6 +13,9 @@ use std::error::Error;
 pub struct RandomCommand {
     n: usize,
     tags: Option<Vec<String>>,
+    format: Format,
+    template: Option<String>,
+    color: ColorChoice,
 }
 
 impl RandomCommand {
@@ -17,11 +26,33 @@ impl RandomCommand {
             .values_of("tag")
             .map(|i| i.map(String::from).collect::<Vec<_>>());
 
-        Self { n, tags }
+        let format = m
+            .value_of("format")
+            .map(|s| {
+                s.parse()
+                    .expect("internal error: Format::from_str returned an error")
+            })
+            .unwrap_or_default();
+
+        Self {
+            n,
+            tags,
+            format,
+            template: m.value_of("template").map(String::from),
+            color: m
+                .value_of("color")
+                .map(|s| {
+                    s.parse()
+                        .expect("internal error: ColorChoice::from_str returned an error")
+                })
+                .unwrap_or_default(),
+        }
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         let c = Config::get()?;
+        let template = template::resolve(self.template.as_deref(), c.list_template.as_deref())?;
+        let renderer = Renderer::new(&c.theme, self.color)?;
 
         if let Some(tags) = self.tags.as_mut() {
             for t in tags.iter_mut() {
@@ -31,17 +62,18 @@ impl RandomCommand {
 
         let notes: Vec<_> = note::get_notes(&c.todos_file)?
             .into_iter()
-            .filter(|n| match self.tags.as_ref() {
+            .enumerate()
+            .filter(|(_, n)| !n.is_done())
+            .filter(|(_, n)| match self.tags.as_ref() {
                 None => true,
                 Some(tags) => match n.tags.as_ref() {
                     None => false,
                     Some(t) => is_match(tags, t),
                 },
             })
-            .enumerate()
             .collect();
 
-        if notes.is_empty() {
+        if notes.is_empty() && self.format == Format::Human {
             if self.tags.is_none() {
                 println!("you have no notes");
             } else {
@@ -53,8 +85,7 @@ impl RandomCommand {
         let mut rng = &mut rand::thread_rng();
         let notes: Vec<_> = notes.choose_multiple(&mut rng, self.n).collect();
 
-        note::print_notes_enumerated(&notes);
-        Ok(())
+        format::print_notes(self.format, template.as_ref(), &renderer, &notes, false)
     }
 }
 
@@ -1,98 +1,137 @@
-use super::{
-    index::{Index, MinMax},
-    query::Filter,
-};
+use super::query::Selector;
 
 use crate::{
-    config::Config,
-    note::{self, Notes},
+    config::{
+        hook::{Context, Rewrite},
+        Config,
+    },
+    journal::{self, Entry, Snapshot},
+    note::{self, Note, Notes},
 };
 
 use clap::ArgMatches;
-use glob::Pattern;
 
-use std::error::Error;
+use std::{
+    error::Error,
+    io::{self, BufRead, IsTerminal, Write},
+};
 
 #[derive(Debug)]
 pub struct RemoveCommand {
-    pub index: Option<Index>,
-    pub filter: Filter,
+    pub selector: Selector,
+    pub dry_run: bool,
+    pub yes: bool,
+}
+
+fn print_list<'a>(notes: impl IntoIterator<Item = &'a Note>) {
+    for n in notes {
+        println!("-  {}  {}", n.id, n.title);
+    }
+}
+
+fn confirm(prompt: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(&answer.trim().to_lowercase()[..], "y" | "yes"))
 }
 
 impl RemoveCommand {
     pub fn from_matches(m: &ArgMatches) -> Self {
-        let titles = m.values_of("title").map(|i| {
-            i.map(|s| {
-                Pattern::new(s).unwrap_or_else(|e| {
-                    panic!("invalid glob pattern {}: {:?}", s, e);
-                })
-            })
-            .collect::<Vec<_>>()
-        });
-
-        let lvl = m
-            .value_of("lvl")
-            .map(|s| MinMax::parse(s).expect("internal error: MinMax::parse returned none"));
-        let index = m
-            .value_of("index")
-            .map(|s| Index::parse(s).expect("internal error: Index::parse returned none"));
-        let tags = m
-            .values_of("tag")
-            .map(|i| i.map(String::from).collect::<Vec<_>>());
         Self {
-            index,
-            filter: Filter { titles, lvl, tags },
+            selector: Selector::from_matches(m),
+            dry_run: m.is_present("dry-run"),
+            yes: m.is_present("yes"),
         }
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         let c = Config::get()?;
 
-        if let Err(e) = c.hooks.run_pre_remove() {
-            match c.abort_on_hook_error {
-                Some(true) | None => return Err(Box::new(e)),
-                Some(false) => println!("pre-remove hook error: {:?}", e),
-            };
-        }
-
+        // the selection is confirmed without holding the lock, the notes are loaded again afterwards
         let notes = note::get_notes(&c.todos_file)?;
-
         if notes.is_empty() {
             println!("you have no todos");
             return Ok(());
         }
-        if let Some(i) = self.index.as_mut() {
-            i.calibrate(notes.len());
+        let selected: Vec<_> = self
+            .selector
+            .select(&notes)?
+            .into_iter()
+            .map(|i| (i, &notes[i]))
+            .collect();
+        if selected.is_empty() {
+            println!("no match, nothing to do");
+            return Ok(());
+        }
+
+        if self.dry_run {
+            if selected.len() == 1 {
+                println!("would delete 1 note:");
+            } else {
+                println!("would delete {} notes:", selected.len());
+            }
+            print_list(selected.iter().map(|(_, n)| *n));
+            return Ok(());
+        }
+
+        if !self.yes && c.should_confirm_remove(selected.len()) && io::stdin().is_terminal() {
+            print_list(selected.iter().map(|(_, n)| *n));
+            let prompt = if selected.len() == 1 {
+                String::from("delete 1 note?")
+            } else {
+                format!("delete {} notes?", selected.len())
+            };
+            if !confirm(&prompt)? {
+                println!("aborted, nothing was deleted");
+                return Ok(());
+            }
+        }
+        let mut ctx = Context::new("remove", &c.todos_file, &c.list)
+            .with_notes(selected)
+            .with_rewrite(Rewrite::Select);
+        c.run_pre_hooks(&mut ctx)?;
+        // the pre hooks may have left some of them out
+        let ids: Vec<_> = ctx.notes.into_iter().map(|(_, n)| n.id).collect();
+        if ids.is_empty() {
+            println!("the hooks left no note to delete, nothing to do");
+            return Ok(());
         }
 
-        let (remaining, deleted): (Vec<_>, Vec<_>) =
-            notes.into_iter().enumerate().partition(|(i, n)| {
-                let not_in_range = if let Some(idx) = &self.index {
-                    !idx.in_range(*i as isize)
-                } else {
-                    true
-                };
-                not_in_range && (self.filter.is_empty() || !self.filter.is_match(n))
-            });
+        let lock = c.lock()?;
+        let notes = note::get_notes(&c.todos_file)?;
+        let (deleted, remaining): (Vec<_>, Vec<_>) = notes
+            .into_iter()
+            .enumerate()
+            .partition(|(_, n)| ids.contains(&n.id));
 
         if deleted.is_empty() {
-            println!("no match, nothing to do");
+            println!("the notes were removed by another process, nothing to do");
             return Ok(());
         }
         let notes = Notes::new(remaining.into_iter().map(|(_, n)| n).collect());
+        let entry = Entry::new(
+            "remove",
+            journal::summary("remove", deleted.iter().map(|(_, n)| n)),
+            deleted.iter().map(|(i, n)| Snapshot::new(*i, n)).collect(),
+            Vec::new(),
+        );
 
-        notes.save_to(&c.todos_file)?;
+        c.save_change(&notes, entry)?;
+        drop(lock);
 
         if deleted.len() == 1 {
             println!("deleted 1 note:");
         } else {
             println!("deleted {} notes:", deleted.len());
         }
-        for (_, n) in &deleted {
-            println!("-  {}", n.title);
-        }
+        print_list(deleted.iter().map(|(_, n)| n));
 
-        c.hooks.run_post_remove().map_err(Box::new)?;
+        let ctx = Context::new("remove", &c.todos_file, &c.list)
+            .with_notes(deleted.iter().map(|(i, n)| (*i, n)));
+        c.hooks.run_on_change(&ctx)?;
+        c.hooks.run_post(&ctx)?;
         Ok(())
     }
 }
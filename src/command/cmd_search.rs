@@ -0,0 +1,86 @@
+use super::query;
+
+use crate::{
+    config::Config,
+    format::{self, Format},
+    note,
+    render::{ColorChoice, Renderer},
+    search::Search,
+    template,
+};
+
+use clap::ArgMatches;
+
+use std::error::Error;
+
+/// Searches the notes and displays the matching ones by relevance.
+#[derive(Debug)]
+pub struct SearchCommand {
+    pub search: Search,
+    /// `Some(true)` searches only completed notes, `Some(false)` only the pending ones.
+    pub done: Option<bool>,
+    pub show_time: bool,
+    pub format: Format,
+    pub template: Option<String>,
+    pub color: ColorChoice,
+}
+
+impl SearchCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        let search = query::search_from_matches(m, "pattern")
+            .expect("internal error: search_from_matches returned None");
+        let done = if m.is_present("all") {
+            None
+        } else {
+            Some(m.is_present("done"))
+        };
+
+        Self {
+            search,
+            done,
+            show_time: m.is_present("time"),
+            format: m
+                .value_of("format")
+                .map(|s| {
+                    s.parse()
+                        .expect("internal error: Format::from_str returned an error")
+                })
+                .unwrap_or_default(),
+            template: m.value_of("template").map(String::from),
+            color: m
+                .value_of("color")
+                .map(|s| {
+                    s.parse()
+                        .expect("internal error: ColorChoice::from_str returned an error")
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let template = template::resolve(self.template.as_deref(), c.list_template.as_deref())?;
+        let matcher = self.search.matcher()?;
+        let renderer = Renderer::new(&c.theme, self.color)?.with_search(&matcher);
+
+        let notes = note::get_notes(&c.todos_file)?;
+        let mut hits: Vec<_> = notes
+            .into_iter()
+            .enumerate()
+            .filter(|(_, n)| self.done.is_none_or(|d| n.is_done() == d))
+            .filter_map(|(i, n)| matcher.search(&n).map(|h| (h.score, (i, n))))
+            .collect();
+
+        // notes are printed in reverse, so the best match comes last here to be printed first
+        hits.sort_by(|(a, (i, _)), (b, (j, _))| a.cmp(b).then(j.cmp(i)));
+        let notes: Vec<_> = hits.iter().map(|(_, n)| n).collect();
+
+        format::print_notes(
+            self.format,
+            template.as_ref(),
+            &renderer,
+            &notes,
+            self.show_time,
+        )
+    }
+}
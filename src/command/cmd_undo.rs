@@ -0,0 +1,211 @@
+use crate::{
+    config::{hook::Context, Config},
+    journal::{Journal, Snapshot},
+    note::{self, Notes},
+};
+
+use clap::ArgMatches;
+
+use std::{error::Error, path::PathBuf};
+
+/// Undoes the last `n` operations, or redoes the last `n` undone operations if `redo` is true.
+#[derive(Debug)]
+pub struct UndoCommand {
+    pub n: usize,
+    pub redo: bool,
+}
+
+impl UndoCommand {
+    pub fn from_matches(m: &ArgMatches, redo: bool) -> Self {
+        let n = m.value_of("n").unwrap().parse::<usize>().unwrap();
+        Self { n, redo }
+    }
+
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let command = if self.redo { "redo" } else { "undo" };
+        let p = c.journal_path();
+        // the pre hooks run without holding the lock, the journal is loaded again afterwards
+        let journal = Journal::load(&p)?;
+        c.run_pre_hooks(
+            &mut Context::new(command, &c.todos_file, &c.list).with_notes(
+                self.affected(&journal)
+                    .into_iter()
+                    .map(|s| (s.index, &s.note)),
+            ),
+        )?;
+
+        // the other lists the entries changed, such as the ones notes were moved to, are locked along with this one.
+        // they're read from the journal before locking, so it's read again in case another process changed it meanwhile
+        let (locks, mut journal, others) = loop {
+            let linked = self.linked(&Journal::load(&p)?);
+            let others = linked
+                .iter()
+                .map(|(name, file)| {
+                    let o = Config::get_list(Some(name))?;
+                    if &o.todos_file != file {
+                        return Err(format!(
+                            "the list {} was moved from {} to {}, the changes made to it can't be {}",
+                            name,
+                            file.display(),
+                            o.todos_file.display(),
+                            if self.redo { "redone" } else { "undone" }
+                        )
+                        .into());
+                    }
+                    Ok(o)
+                })
+                .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+            let mut lists = vec![&c];
+            lists.extend(&others);
+            let locks = Config::lock_all(&lists)?;
+            let journal = Journal::load(&p)?;
+            if self.linked(&journal) == linked {
+                break (locks, journal, others);
+            }
+        };
+        let mut notes = note::get_notes(&c.todos_file)?;
+        let originals = others
+            .iter()
+            .map(|o| note::get_notes(&o.todos_file))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut other_notes = originals.clone();
+
+        let mut summaries = Vec::with_capacity(self.n);
+        // the ids of the notes the entries change, for the hooks
+        let mut ids = Vec::new();
+        for _ in 0..self.n {
+            let entry = if self.redo {
+                journal.first_undone()
+            } else {
+                journal.last_done()
+            };
+            let entry = match entry {
+                Some(e) => e,
+                None => break,
+            };
+
+            let mut res = if self.redo {
+                entry.redo(&mut notes)
+            } else {
+                entry.undo(&mut notes)
+            };
+            if let (Ok(_), Some(l)) = (&res, &entry.linked) {
+                let i = others
+                    .iter()
+                    .position(|o| o.list == l.list)
+                    .expect("internal error: the linked list wasn't locked");
+                res = if self.redo {
+                    l.redo(&mut other_notes[i])
+                } else {
+                    l.undo(&mut other_notes[i])
+                };
+            }
+            res.map_err(|e| {
+                format!(
+                    "can't {} \"{}\": {}",
+                    if self.redo { "redo" } else { "undo" },
+                    entry.summary,
+                    e
+                )
+            })?;
+            entry.undone = !self.redo;
+            ids.extend(
+                entry
+                    .before
+                    .iter()
+                    .chain(&entry.after)
+                    .map(|s| s.note.id.clone()),
+            );
+            summaries.push(entry.summary.clone());
+        }
+
+        if summaries.is_empty() {
+            println!("nothing to {}", if self.redo { "redo" } else { "undo" });
+            return Ok(());
+        }
+
+        // the other lists are saved first: if saving this one fails, they're restored and nothing was undone
+        for (o, n) in others.iter().zip(other_notes) {
+            o.save_notes(&Notes::new(n))?;
+        }
+        let notes = Notes::new(notes);
+        if let Err(e) = c.save_notes(&notes) {
+            for (o, n) in others.iter().zip(originals) {
+                if let Err(e2) = o.save_notes(&Notes::new(n)) {
+                    return Err(format!(
+                        "{}; restoring {} also failed: {}",
+                        e,
+                        o.todos_file.display(),
+                        e2
+                    )
+                    .into());
+                }
+            }
+            return Err(e);
+        }
+        journal.save(&p)?;
+        let message = match &summaries[..] {
+            [s] => format!("{} {}", command, s),
+            _ => format!("{} {} changes", command, summaries.len()),
+        };
+        for o in others.iter().chain(Some(&c)) {
+            o.commit(&message)?;
+        }
+        drop(locks);
+
+        for s in &summaries {
+            println!("{} {}", if self.redo { "redid" } else { "undid" }, s);
+        }
+
+        // the notes that were removed aren't passed to the hooks
+        let ctx = Context::new(command, &c.todos_file, &c.list).with_notes(
+            notes
+                .todo
+                .iter()
+                .flatten()
+                .enumerate()
+                .filter(|(_, n)| ids.contains(&n.id)),
+        );
+        c.hooks.run_on_change(&ctx)?;
+        c.hooks.run_post(&ctx)?;
+        Ok(())
+    }
+
+    /// Returns the notes the entries that would be undone or redone change, as they'll be afterwards,
+    /// or as they are if they'll be removed.
+    fn affected<'a>(&self, journal: &'a Journal) -> Vec<&'a Snapshot> {
+        let mut notes: Vec<&Snapshot> = Vec::new();
+        for e in journal.pending(self.n, self.redo) {
+            let (old, new) = if self.redo {
+                (&e.before, &e.after)
+            } else {
+                (&e.after, &e.before)
+            };
+            for s in new {
+                notes.retain(|n| n.note.id != s.note.id);
+                notes.push(s);
+            }
+            for s in old {
+                if !new.iter().any(|n| n.note.id == s.note.id) {
+                    notes.retain(|n| n.note.id != s.note.id);
+                    notes.push(s);
+                }
+            }
+        }
+        notes
+    }
+
+    /// Returns the other lists the entries that would be undone or redone changed, with their todos files.
+    fn linked(&self, journal: &Journal) -> Vec<(String, PathBuf)> {
+        let mut lists: Vec<_> = journal
+            .pending(self.n, self.redo)
+            .into_iter()
+            .filter_map(|e| e.linked.as_ref())
+            .map(|l| (l.list.clone(), l.file.clone()))
+            .collect();
+        lists.sort();
+        lists.dedup();
+        lists
+    }
+}
@@ -0,0 +1,94 @@
+use super::{app, cmd_list::ListCommand, set_global_env};
+
+use crate::config::Config;
+
+use clap::{AppSettings, ArgMatches};
+
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct ViewCommand {
+    /// The view to display; the views are listed if it's None.
+    pub name: Option<String>,
+    /// The list arguments given after the name.
+    pub args: Vec<String>,
+}
+
+impl ViewCommand {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        Self {
+            name: m.value_of("name").map(String::from),
+            args: m
+                .values_of("args")
+                .map(|i| i.map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let c = Config::get()?;
+        let name = match &self.name {
+            Some(name) => name,
+            None => {
+                print_views(&c);
+                return Ok(());
+            }
+        };
+
+        let view = c.views.get(name).ok_or_else(|| {
+            if c.views.is_empty() {
+                format!(
+                    "unknown view {}, no views are defined in the config file",
+                    name
+                )
+            } else {
+                let names: Vec<_> = c.views.keys().map(String::as_str).collect();
+                format!(
+                    "unknown view {}, the defined views are {}",
+                    name,
+                    names.join(", ")
+                )
+            }
+        })?;
+
+        let list = || {
+            // the global options may come after the name, or after `todo list @NAME`
+            app::app_list()
+                .args(app::args_global())
+                .bin_name(format!("todo view {}", name))
+                .setting(AppSettings::AllArgsOverrideSelf)
+        };
+        // check the view on its own first, so its mistakes aren't blamed on the command line
+        list()
+            .try_get_matches_from(std::iter::once("list".to_owned()).chain(view.args(&[])))
+            .map_err(|e| {
+                let e = e.to_string();
+                let reason = e.lines().next().unwrap_or_default();
+                format!(
+                    "invalid view {} in the config file: {}",
+                    name,
+                    reason.trim_start_matches("error: ")
+                )
+            })?;
+
+        let m = list()
+            .get_matches_from(std::iter::once("list".to_owned()).chain(view.args(&self.args)));
+        set_global_env(&m);
+        ListCommand::from_matches(&m).run()
+    }
+}
+
+fn print_views(c: &Config) {
+    if c.views.is_empty() {
+        println!("no views are defined in the config file");
+        return;
+    }
+    let width = c.views.keys().map(|s| s.chars().count()).max().unwrap_or(0);
+    for (name, view) in &c.views {
+        let about = view
+            .description
+            .clone()
+            .unwrap_or_else(|| view.args(&[]).join(" "));
+        println!("{:width$}  {}", name, about, width = width);
+    }
+}
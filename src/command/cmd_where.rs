@@ -1,39 +1,69 @@
-use crate::config::{self, Config};
+use crate::{
+    config::{self, Config},
+    format::{self, Format},
+};
 use clap::ArgMatches;
 use std::error::Error;
 
 #[derive(Debug)]
-pub enum WhereCommand {
+pub enum Paths {
     All,
     Config,
     Todos,
 }
 
+#[derive(Debug)]
+pub struct WhereCommand {
+    pub paths: Paths,
+    pub format: Format,
+}
+
 impl WhereCommand {
     pub fn from_matches(m: &ArgMatches) -> Self {
-        if m.is_present("config") {
-            Self::Config
+        let paths = if m.is_present("config") {
+            Paths::Config
         } else if m.is_present("todos") {
-            Self::Todos
+            Paths::Todos
         } else {
-            Self::All
-        }
+            Paths::All
+        };
+        let format = m
+            .value_of("format")
+            .map(|s| {
+                s.parse()
+                    .expect("internal error: Format::from_str returned an error")
+            })
+            .unwrap_or_default();
+
+        Self { paths, format }
     }
 
     pub fn run(&self) -> Result<(), Box<dyn Error>> {
-        match self {
-            Self::Config => config::config_path_checked().map(|p| {
-                println!("{}", p.display());
-            }),
-            Self::Todos => Config::get().map(|c| {
-                println!("{}", c.todos_file.display());
-            }),
-            Self::All => {
-                let p = config::config_path_checked()?;
-                let c = Config::get()?;
-                println!("todos: {}\nconfig: {}", c.todos_file.display(), p.display());
-                Ok(())
+        let mut values = Vec::with_capacity(3);
+        let mut list = None;
+        if let Paths::All | Paths::Todos = self.paths {
+            let c = Config::get()?;
+            values.push(("todos", c.todos_file.display().to_string()));
+            list = Some(c.list);
+        }
+        if let Paths::All | Paths::Config = self.paths {
+            let p = config::config_path_checked()?;
+            values.push(("config", p.display().to_string()));
+        }
+        // the name of the list the todos file belongs to, such as project
+        if let (Paths::All, Some(list)) = (&self.paths, list) {
+            values.push(("list", list));
+        }
+
+        match (&self.paths, self.format) {
+            (Paths::All, Format::Human) => {
+                for (k, v) in &values {
+                    println!("{}: {}", k, v);
+                }
             }
+            (_, Format::Human) => println!("{}", values[0].1),
+            (_, f) => print!("{}", format::render_values(f, &values)?),
         }
+        Ok(())
     }
 }
@@ -1,21 +1,46 @@
+mod alias;
 mod app;
+mod cmd_completions;
+mod cmd_done;
+mod cmd_edit;
+mod cmd_git;
+mod cmd_history;
 mod cmd_list;
+mod cmd_lists;
+mod cmd_move;
 mod cmd_new;
 mod cmd_random;
 mod cmd_remove;
+mod cmd_search;
+mod cmd_undo;
+mod cmd_view;
 mod cmd_where;
+mod expr;
 mod index;
 mod query;
+mod sort;
 
-use std::error::Error;
+use crate::config::{hook::Context, Config};
 
+use std::{env, error::Error};
+
+use cmd_completions::CompletionsCommand;
+use cmd_done::DoneCommand;
+use cmd_edit::EditCommand;
+use cmd_git::GitCommand;
+use cmd_history::HistoryCommand;
 use cmd_list::ListCommand;
+use cmd_lists::ListsCommand;
+use cmd_move::MoveCommand;
 use cmd_new::NewCommand;
 use cmd_random::RandomCommand;
 use cmd_remove::RemoveCommand;
+use cmd_search::SearchCommand;
+use cmd_undo::UndoCommand;
+use cmd_view::ViewCommand;
 use cmd_where::WhereCommand;
 
-use clap::App;
+use clap::{App, ArgMatches};
 
 pub struct Command;
 
@@ -25,21 +50,135 @@ impl Command {
     }
 
     pub fn run() -> Result<(), Box<dyn Error>> {
-        let matches = Self::app().get_matches();
-        if let Some(cmd) = matches.subcommand_name() {
-            let m = matches
-                .subcommand_matches(cmd)
-                .expect("internal error: arg matches is empty");
-            match cmd {
-                "list" => ListCommand::from_matches(m).run(),
-                "remove" => RemoveCommand::from_matches(m).run(),
-                "where" => WhereCommand::from_matches(m).run(),
-                "new" => NewCommand::from_matches(m).run(),
-                "random" => RandomCommand::from_matches(m).run(),
-                _ => panic!("internal error: unknown command {}", cmd),
+        let mut app = Self::app();
+        let mut args: Vec<String> = env::args().collect();
+        let builtins = alias::builtins(&app);
+        let commands: Vec<_> = app.get_subcommands().map(|a| a.get_name()).collect();
+        let pos = alias::position(&app, &args);
+        let config = match Config::get() {
+            Ok(c) => Some(c),
+            // the built-in commands report the error themselves if they need the config
+            Err(_) if pos.is_none_or(|i| builtins.contains(&args[i])) => None,
+            Err(e) => return Err(e),
+        };
+        if let Some(c) = &config {
+            for w in c.hooks.warnings(&commands) {
+                eprintln!("warning: {}", w);
+            }
+        }
+        if let Some(c) = config.as_ref().filter(|c| !c.aliases.is_empty()) {
+            for w in alias::warnings(&c.aliases, &builtins) {
+                eprintln!("warning: {}", w);
+            }
+            if let Some(i) = pos {
+                args = alias::expand(args, i, &c.aliases, &builtins)?;
+            }
+            app = app::app_with(&[], &alias::subcommands(&c.aliases, &builtins));
+        }
+
+        let pos = alias::position(&app, &args);
+        let matches = app.get_matches_from(&args);
+        // the global options are in the matches of the subcommand if there's one
+        let global = match matches.subcommand() {
+            Some((_, m)) => m,
+            None => &matches,
+        };
+        set_global_env(global);
+        // loaded again for the list given, so the hooks are told about its file;
+        // if that fails the command fails the same way, and the on_error hooks still run
+        let config = match config {
+            Some(c) if global.is_present("list") || global.is_present("global") => {
+                Config::get().ok().or(Some(c))
+            }
+            c => c,
+        };
+
+        let cmd = matches.subcommand_name().unwrap_or("list");
+        // the commands that change the notes run their hooks themselves, when they take effect
+        let hooked = config.as_ref().filter(|_| !MUTATING.contains(&cmd));
+        let res: Result<(), Box<dyn Error>> = (|| {
+            if let Some(c) = hooked {
+                c.run_pre_hooks(&mut Context::new(cmd, &c.todos_file, &c.list))?;
+            }
+            dispatch(&matches, &args[pos.map_or(args.len(), |i| i + 1)..])?;
+            if let Some(c) = hooked {
+                c.hooks
+                    .run_post(&Context::new(cmd, &c.todos_file, &c.list))?;
+            }
+            Ok(())
+        })();
+
+        if let (Err(e), Some(c)) = (&res, &config) {
+            let ctx = Context {
+                error: Some(e.to_string()),
+                ..Context::new(cmd, &c.todos_file, &c.list)
+            };
+            if let Err(e) = c.hooks.run_on_error(&ctx) {
+                println!("on-error hook error: {}", e);
             }
-        } else {
-            ListCommand::default().run()
         }
+        res
     }
 }
+
+/// Sets the env variables of the global options in `m`; Config::get reads them, and the hooks inherit them.
+fn set_global_env(m: &ArgMatches) {
+    if let Some(list) = m.value_of("list") {
+        env::set_var("TODO_LIST", list);
+    }
+    if m.is_present("global") {
+        env::set_var("TODO_GLOBAL", "1");
+    }
+}
+
+/// The commands that change the notes.
+const MUTATING: &[&str] = &[
+    "new", "remove", "edit", "done", "reopen", "undo", "redo", "move",
+];
+
+/// Runs the subcommand in `matches`; `args` are the arguments after its name.
+fn dispatch(matches: &ArgMatches, args: &[String]) -> Result<(), Box<dyn Error>> {
+    if let Some(cmd) = matches.subcommand_name() {
+        let m = matches
+            .subcommand_matches(cmd)
+            .expect("internal error: arg matches is empty");
+        match cmd {
+            "list" => match list_view(m, args) {
+                Some(v) => v.run(),
+                None => ListCommand::from_matches(m).run(),
+            },
+            "remove" => RemoveCommand::from_matches(m).run(),
+            "edit" => EditCommand::from_matches(m).run(),
+            "done" => DoneCommand::from_matches(m, true).run(),
+            "reopen" => DoneCommand::from_matches(m, false).run(),
+            "undo" => UndoCommand::from_matches(m, false).run(),
+            "redo" => UndoCommand::from_matches(m, true).run(),
+            "history" => HistoryCommand::from_matches(m).run(),
+            "where" => WhereCommand::from_matches(m).run(),
+            "new" => NewCommand::from_matches(m).run(),
+            "random" => RandomCommand::from_matches(m).run(),
+            "search" => SearchCommand::from_matches(m).run(),
+            "view" => ViewCommand::from_matches(m).run(),
+            "lists" => ListsCommand.run(),
+            "move" => MoveCommand::from_matches(m).run(),
+            "git" => GitCommand::from_matches(m).run(),
+            "completions" => CompletionsCommand::from_matches(m).run(),
+            _ => panic!("internal error: unknown command {}", cmd),
+        }
+    } else {
+        ListCommand::default().run()
+    }
+}
+
+/// Returns the view to display if `todo list` was given `@NAME`, along with the rest of its arguments,
+/// the ones after the subcommand.
+fn list_view(m: &ArgMatches, args: &[String]) -> Option<ViewCommand> {
+    let name = m.values_of("title")?.find(|s| s.starts_with('@'))?;
+    let mut args = args.to_vec();
+    let i = args.iter().position(|s| s == name)?;
+    args.remove(i);
+    Some(ViewCommand {
+        name: Some(name[1..].to_owned()),
+        args,
+    })
+}
@@ -0,0 +1,723 @@
+//! The `--query` expression language.
+//!
+//! A query is made of terms combined with `and`, `or`, `not` and parentheses;
+//! terms next to each other are combined with `and`, and `not` binds tighter than `and`, which binds tighter than `or`.
+//!
+//! - `tag:GLOB`, `title:GLOB`, `body:GLOB` match the field with a case insensitive glob pattern.
+//! - `tag~REGEX`, `title~REGEX`, `body~REGEX` match the field with a case insensitive regular expression.
+//! - `id:PREFIX` matches the notes whose id starts with the prefix.
+//! - `lvl=3`, `lvl!=3`, `lvl<3`, `lvl<=3`, `lvl>3`, `lvl>=3` compare the importance level; notes without one have the level 0.
+//! - `created`, `updated`, `done` and `due` are compared to dates the same way, such as `created>=3d` or `due<+1w`.
+//! - `is:done`, `is:pending` and `is:overdue` match the state of the note.
+//! - `has:due`, `has:lvl`, `has:tags` and `has:body` match the notes that have the field.
+//! - A word or a quoted string on its own matches the notes whose title, body or tags contain it.
+//!
+//! Values that contain spaces or parentheses must be quoted: `body~"(milk|bread)"`.
+
+use crate::{
+    date,
+    note::{self, Note},
+    search::{Matcher, Mode},
+};
+
+use chrono::{DateTime, Local};
+use glob::{MatchOptions, Pattern};
+use regex::{Regex, RegexBuilder};
+
+use std::str::FromStr;
+
+const FIELDS: &[&str] = &[
+    "tag", "title", "body", "id", "lvl", "created", "updated", "done", "due", "is", "has",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    /// `:`
+    Colon,
+    /// `~`
+    Tilde,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Colon => ":",
+            Self::Tilde => "~",
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    fn compare<T: PartialOrd>(self, a: T, b: T) -> bool {
+        match self {
+            Self::Eq | Self::Colon => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+            Self::Tilde => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Text {
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+impl Text {
+    fn is_match(&self, s: &str) -> bool {
+        const OPT: MatchOptions = MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+        match self {
+            Self::Glob(p) => p.matches_with(s, OPT),
+            Self::Regex(re) => re.is_match(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimeField {
+    Created,
+    Updated,
+    Done,
+    Due,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Done,
+    Pending,
+    Overdue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Has {
+    Due,
+    Lvl,
+    Tags,
+    Body,
+}
+
+#[derive(Debug)]
+enum Term {
+    Tag(Text),
+    Title(Text),
+    Body(Text),
+    Id(String),
+    Lvl(Op, u8),
+    Time(TimeField, Op, DateTime<Local>),
+    Is(State),
+    Has(Has),
+    Text(Matcher),
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+impl Expr {
+    fn is_match(&self, n: &Note) -> bool {
+        match self {
+            Self::And(a, b) => a.is_match(n) && b.is_match(n),
+            Self::Or(a, b) => a.is_match(n) || b.is_match(n),
+            Self::Not(e) => !e.is_match(n),
+            Self::Term(t) => t.is_match(n),
+        }
+    }
+}
+
+impl Term {
+    fn is_match(&self, n: &Note) -> bool {
+        let tags = n.tags.as_deref().unwrap_or_default();
+        match self {
+            Self::Tag(t) => tags.iter().any(|s| t.is_match(s)),
+            Self::Title(t) => t.is_match(&n.title),
+            Self::Body(t) => t.is_match(&n.body),
+            Self::Id(prefix) => n.id.starts_with(prefix.as_str()),
+            Self::Lvl(op, lvl) => op.compare(n.lvl.unwrap_or_default(), *lvl),
+            Self::Time(field, op, t) => {
+                let value = match field {
+                    TimeField::Created => n.created,
+                    TimeField::Updated => n.updated,
+                    TimeField::Done => n.done,
+                    TimeField::Due => n.due,
+                };
+                value.is_some_and(|v| op.compare(v, *t))
+            }
+            Self::Is(State::Done) => n.is_done(),
+            Self::Is(State::Pending) => !n.is_done(),
+            Self::Is(State::Overdue) => n.is_overdue(note::now()),
+            Self::Has(Has::Due) => n.due.is_some(),
+            Self::Has(Has::Lvl) => n.lvl.is_some(),
+            Self::Has(Has::Tags) => !tags.is_empty(),
+            Self::Has(Has::Body) => !n.body.trim().is_empty(),
+            Self::Text(m) => m.is_match(n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// A bare word or a quoted string.
+    Word(String),
+    /// A field, an operator and a value, such as `lvl>=3`.
+    Field(String, Op, String),
+}
+
+/// Returns the column of the byte position `pos` in `s`.
+fn column(s: &str, pos: usize) -> usize {
+    s[..pos].chars().count() + 1
+}
+
+fn error(s: &str, pos: usize, msg: impl AsRef<str>) -> String {
+    format!("query error at column {}: {}", column(s, pos), msg.as_ref())
+}
+
+fn is_op_char(c: char) -> bool {
+    matches!(c, ':' | '~' | '=' | '!' | '<' | '>')
+}
+
+/// Splits the query into tokens, each with its byte position.
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    // reads a quoted string, the opening quote is already consumed
+    let quoted = |chars: &mut std::iter::Peekable<std::str::CharIndices>,
+                  start: usize|
+     -> Result<String, String> {
+        let mut out = String::new();
+        while let Some((_, c)) = chars.next() {
+            match c {
+                '"' => return Ok(out),
+                '\\' => match chars.next() {
+                    Some((_, c)) => out.push(c),
+                    None => break,
+                },
+                c => out.push(c),
+            }
+        }
+        Err(error(s, start, "unclosed quote"))
+    };
+
+    while let Some(&(pos, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((pos, Token::LParen));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((pos, Token::RParen));
+            }
+            '"' => {
+                chars.next();
+                tokens.push((pos, Token::Word(quoted(&mut chars, pos)?)));
+            }
+            c if is_op_char(c) => {
+                return Err(error(s, pos, format!("unexpected {}", c)));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' || is_op_char(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                let op = match chars.peek() {
+                    Some(&(op_pos, c)) if is_op_char(c) => {
+                        chars.next();
+                        let next = chars.peek().map(|&(_, c)| c);
+                        let op = match (c, next) {
+                            (':', _) => Op::Colon,
+                            ('~', _) => Op::Tilde,
+                            ('=', _) => Op::Eq,
+                            ('!', Some('=')) => Op::Ne,
+                            ('<', Some('=')) => Op::Le,
+                            ('>', Some('=')) => Op::Ge,
+                            ('<', _) => Op::Lt,
+                            ('>', _) => Op::Gt,
+                            _ => return Err(error(s, op_pos, format!("unexpected {}", c))),
+                        };
+                        if matches!(op, Op::Ne | Op::Le | Op::Ge) {
+                            chars.next();
+                        }
+                        Some((op_pos, op))
+                    }
+                    _ => None,
+                };
+
+                let token = match op {
+                    None => match &word.to_lowercase()[..] {
+                        "and" => Token::And,
+                        "or" => Token::Or,
+                        "not" => Token::Not,
+                        _ => Token::Word(word),
+                    },
+                    Some((op_pos, op)) => {
+                        let value = match chars.peek() {
+                            Some(&(q, '"')) => {
+                                chars.next();
+                                quoted(&mut chars, q)?
+                            }
+                            _ => {
+                                let mut value = String::new();
+                                while let Some(&(_, c)) = chars.peek() {
+                                    if c.is_whitespace() || c == ')' {
+                                        break;
+                                    }
+                                    value.push(c);
+                                    chars.next();
+                                }
+                                value
+                            }
+                        };
+                        if value.is_empty() {
+                            return Err(error(
+                                s,
+                                op_pos,
+                                format!("expected a value after {}{}", word, op.as_str()),
+                            ));
+                        }
+                        Token::Field(word, op, value)
+                    }
+                };
+                tokens.push((pos, token));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    now: DateTime<Local>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    /// The byte position of the current token, or the end of the query.
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.src.len(), |(pos, _)| *pos)
+    }
+
+    fn error(&self, pos: usize, msg: impl AsRef<str>) -> String {
+        error(self.src, pos, msg)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut e = self.and()?;
+        while let Some(Token::Or) = self.peek() {
+            self.pos += 1;
+            e = Expr::Or(Box::new(e), Box::new(self.and()?));
+        }
+        Ok(e)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut e = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                // terms next to each other are combined with and
+                Some(Token::Not | Token::LParen | Token::Word(_) | Token::Field(..)) => (),
+                _ => return Ok(e),
+            }
+            e = Expr::And(Box::new(e), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if let Some(Token::Not) = self.peek() {
+            self.pos += 1;
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let pos = self.offset();
+        let token = match self.tokens.get_mut(self.pos) {
+            Some((_, t)) => std::mem::replace(t, Token::LParen),
+            None => return Err(self.error(pos, "unexpected end of the query, expected a term")),
+        };
+        self.pos += 1;
+
+        match token {
+            Token::LParen => {
+                let e = self.or()?;
+                match self.peek() {
+                    Some(Token::RParen) => {
+                        self.pos += 1;
+                        Ok(e)
+                    }
+                    _ => Err(self.error(self.offset(), "expected )")),
+                }
+            }
+            Token::RParen => Err(self.error(pos, "unexpected )")),
+            Token::And | Token::Or => Err(self.error(pos, "expected a term before the operator")),
+            Token::Not => unreachable!("internal error: not is parsed in Parser::not"),
+            Token::Word(w) => Matcher::new(&w, Mode::Substring, false)
+                .map(|m| Expr::Term(Term::Text(m)))
+                .map_err(|e| self.error(pos, e)),
+            Token::Field(field, op, value) => self
+                .term(&field, op, &value)
+                .map(Expr::Term)
+                .map_err(|e| self.error(pos, e)),
+        }
+    }
+
+    fn term(&self, field: &str, op: Op, value: &str) -> Result<Term, String> {
+        let invalid_op = || format!("{} can't be used with {}", op.as_str(), field);
+        let text = || match op {
+            Op::Colon => Pattern::new(value)
+                .map(Text::Glob)
+                .map_err(|e| format!("invalid glob pattern {:?}: {}", value, e.msg)),
+            Op::Tilde => RegexBuilder::new(value)
+                .case_insensitive(true)
+                .build()
+                .map(Text::Regex)
+                .map_err(|e| {
+                    let e = e.to_string();
+                    let reason = e
+                        .lines()
+                        .last()
+                        .unwrap_or_default()
+                        .trim_start_matches("error: ");
+                    format!("invalid regex {:?}: {}", value, reason)
+                }),
+            _ => Err(invalid_op()),
+        };
+        let time = |f: TimeField| {
+            if matches!(op, Op::Colon | Op::Tilde) {
+                return Err(invalid_op());
+            }
+            let t = match f {
+                TimeField::Due => date::parse_due(value, self.now),
+                _ => date::parse_past(value, self.now),
+            };
+            t.map(|t| Term::Time(f, op, t))
+                .ok_or_else(|| format!("invalid date {:?}", value))
+        };
+
+        match &field.to_lowercase()[..] {
+            "tag" => text().map(Term::Tag),
+            "title" => text().map(Term::Title),
+            "body" => text().map(Term::Body),
+            "id" if op == Op::Colon => Ok(Term::Id(value.to_lowercase())),
+            "lvl" if op != Op::Tilde => value.parse().map(|n| Term::Lvl(op, n)).map_err(|_| {
+                format!(
+                    "invalid level {:?}, must be a number between 0 and 255",
+                    value
+                )
+            }),
+            "created" => time(TimeField::Created),
+            "updated" => time(TimeField::Updated),
+            "done" => time(TimeField::Done),
+            "due" => time(TimeField::Due),
+            "is" if op == Op::Colon => match &value.to_lowercase()[..] {
+                "done" => Ok(Term::Is(State::Done)),
+                "pending" => Ok(Term::Is(State::Pending)),
+                "overdue" => Ok(Term::Is(State::Overdue)),
+                _ => Err(format!(
+                    "unknown state {:?}, possible values are done, pending and overdue",
+                    value
+                )),
+            },
+            "has" if op == Op::Colon => match &value.to_lowercase()[..] {
+                "due" => Ok(Term::Has(Has::Due)),
+                "lvl" => Ok(Term::Has(Has::Lvl)),
+                "tags" => Ok(Term::Has(Has::Tags)),
+                "body" => Ok(Term::Has(Has::Body)),
+                _ => Err(format!(
+                    "unknown field {:?}, possible values are due, lvl, tags and body",
+                    value
+                )),
+            },
+            f if FIELDS.contains(&f) => Err(invalid_op()),
+            _ => Err(format!(
+                "unknown field {:?}, possible fields are {}",
+                field,
+                FIELDS.join(", ")
+            )),
+        }
+    }
+}
+
+/// A parsed query.
+#[derive(Debug)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn is_match(&self, n: &Note) -> bool {
+        self.expr.is_match(n)
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser {
+            src: s,
+            tokens: tokenize(s)?,
+            pos: 0,
+            now: note::now(),
+        };
+        let expr = p.or()?;
+        match p.peek() {
+            None => Ok(Self { expr }),
+            Some(Token::RParen) => Err(p.error(p.offset(), "unmatched )")),
+            Some(_) => Err(p.error(p.offset(), "expected and, or or the end of the query")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, body: &str) -> Note {
+        Note::new(title, body)
+    }
+
+    /// Returns the titles of the notes the query matches.
+    fn titles<'a>(query: &str, notes: &'a [Note]) -> Vec<&'a str> {
+        let q: Query = query.parse().unwrap();
+        notes
+            .iter()
+            .filter(|n| q.is_match(n))
+            .map(|n| n.title.as_str())
+            .collect()
+    }
+
+    fn fruits() -> Vec<Note> {
+        ["apple", "banana", "cherry", "apple banana", "banana cherry"]
+            .iter()
+            .map(|t| note(t, ""))
+            .collect()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let notes = fruits();
+        assert_eq!(
+            titles("apple or banana and cherry", &notes),
+            ["apple", "apple banana", "banana cherry"]
+        );
+        assert_eq!(
+            titles("(apple or banana) and cherry", &notes),
+            ["banana cherry"]
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        let notes = fruits();
+        assert_eq!(
+            titles("not apple and banana", &notes),
+            ["banana", "banana cherry"]
+        );
+        assert_eq!(
+            titles("not (apple and banana)", &notes),
+            ["apple", "banana", "cherry", "banana cherry"]
+        );
+        assert_eq!(
+            titles("not not cherry", &notes),
+            ["cherry", "banana cherry"]
+        );
+    }
+
+    #[test]
+    fn terms_next_to_each_other_are_and() {
+        let notes = fruits();
+        assert_eq!(titles("apple banana", &notes), ["apple banana"]);
+        assert_eq!(
+            titles("apple banana or cherry", &notes),
+            titles("(apple and banana) or cherry", &notes)
+        );
+        assert_eq!(
+            titles("banana not cherry", &notes),
+            ["banana", "apple banana"]
+        );
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces_and_parentheses() {
+        let notes = vec![
+            note("buy milk", "at the (corner) shop"),
+            note("buy", "some bread"),
+            note("milk", ""),
+        ];
+        assert_eq!(titles(r#"title:"buy milk""#, &notes), ["buy milk"]);
+        assert_eq!(titles(r#""buy milk""#, &notes), ["buy milk"]);
+        assert_eq!(titles(r#"body~"(milk|bread)""#, &notes), ["buy"]);
+        assert_eq!(titles(r#"body:"*(corner)*""#, &notes), ["buy milk"]);
+        assert_eq!(titles(r#"(body:"*(corner)*")"#, &notes), ["buy milk"]);
+        assert_eq!(
+            titles(r#""say \"hi\"" or milk"#, &notes),
+            ["buy milk", "milk"]
+        );
+        // unquoted, the parenthesis ends the value
+        assert!(r"body~(milk|bread)".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn lvl_comparisons() {
+        let notes: Vec<_> = [None, Some(1), Some(3), Some(5)]
+            .iter()
+            .map(|&lvl| Note {
+                lvl,
+                ..note(&lvl.unwrap_or_default().to_string(), "")
+            })
+            .collect();
+        for (query, want) in &[
+            ("lvl=3", &["3"][..]),
+            ("lvl!=3", &["0", "1", "5"]),
+            ("lvl<3", &["0", "1"]),
+            ("lvl<=3", &["0", "1", "3"]),
+            ("lvl>3", &["5"]),
+            ("lvl>=3", &["3", "5"]),
+            ("lvl:3", &["3"]),
+        ] {
+            assert_eq!(&titles(query, &notes), want, "{}", query);
+        }
+        assert!("lvl~3".parse::<Query>().is_err());
+        assert!("lvl>=256".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn date_comparisons() {
+        let at = |s: &str| date::parse_date(s, note::now());
+        for field in &["created", "updated", "done", "due"] {
+            let notes: Vec<_> = [
+                ("old", at("2020-01-01 00:00")),
+                ("new", at("2030-01-01 00:00")),
+                ("none", None),
+            ]
+            .iter()
+            .map(|&(title, t)| {
+                let mut n = note(title, "");
+                n.created = None;
+                n.updated = None;
+                match *field {
+                    "created" => n.created = t,
+                    "updated" => n.updated = t,
+                    "done" => n.done = t,
+                    _ => n.due = t,
+                }
+                n
+            })
+            .collect();
+            for (op, want) in &[
+                ("=", &["old"][..]),
+                ("!=", &["new"]),
+                ("<", &[]),
+                ("<=", &["old"]),
+                (">", &["new"]),
+                (">=", &["old", "new"]),
+            ] {
+                let query = format!(r#"{}{}"2020-01-01 00:00""#, field, op);
+                assert_eq!(&titles(&query, &notes), want, "{}", query);
+            }
+            assert!(format!("{}:2020-01-01", field).parse::<Query>().is_err());
+            assert!(format!("{}>=someday", field).parse::<Query>().is_err());
+        }
+    }
+
+    #[test]
+    fn relative_dates() {
+        let now = note::now();
+        let notes = vec![
+            Note {
+                created: Some(now - chrono::Duration::try_days(10).unwrap()),
+                due: Some(now + chrono::Duration::try_days(2).unwrap()),
+                ..note("soon", "")
+            },
+            Note {
+                created: Some(now - chrono::Duration::try_hours(1).unwrap()),
+                due: Some(now + chrono::Duration::try_days(30).unwrap()),
+                ..note("later", "")
+            },
+        ];
+        assert_eq!(titles("created>=3d", &notes), ["later"]);
+        assert_eq!(titles("created<3d", &notes), ["soon"]);
+        assert_eq!(titles("due<+1w", &notes), ["soon"]);
+        assert_eq!(titles("due>=+1w", &notes), ["later"]);
+    }
+
+    fn err(query: &str) -> String {
+        query.parse::<Query>().unwrap_err()
+    }
+
+    #[test]
+    fn errors_report_the_column() {
+        assert!(err("lvl>=x").starts_with("query error at column 1: invalid level"));
+        assert!(err("title:a unknown:b").starts_with("query error at column 9: unknown field"));
+        assert_eq!(
+            err("apple and (banana"),
+            "query error at column 18: expected )"
+        );
+        assert_eq!(err("apple )"), "query error at column 7: unmatched )");
+        assert_eq!(
+            err("apple or"),
+            "query error at column 9: unexpected end of the query, expected a term"
+        );
+        assert_eq!(
+            err("apple \"pie"),
+            "query error at column 7: unclosed quote"
+        );
+        assert_eq!(
+            err("lvl>="),
+            "query error at column 4: expected a value after lvl>="
+        );
+        assert!(err(r#"body~"(milk""#).starts_with("query error at column 1: invalid regex"));
+        // columns are counted in characters, not bytes
+        assert_eq!(
+            err("\"é\" or"),
+            "query error at column 7: unexpected end of the query, expected a term"
+        );
+        assert_eq!(err("été )"), "query error at column 5: unmatched )");
+    }
+}
@@ -1,26 +1,51 @@
-use super::index::MinMax;
-use crate::note::Note;
+use super::{
+    expr::Query,
+    index::{Index, MinMax},
+};
+use crate::{
+    note::{self, Note},
+    search::{Matcher, Mode, Search},
+};
 
+use chrono::{DateTime, Local};
+
+use clap::ArgMatches;
 use glob::{MatchOptions, Pattern};
 
-#[derive(Debug)]
+use std::error::Error;
+
+#[derive(Debug, Default)]
 pub struct Filter {
+    pub ids: Option<Vec<String>>,
     pub titles: Option<Vec<Pattern>>,
     pub lvl: Option<MinMax>,
     pub tags: Option<Vec<String>>,
+    /// `Some(true)` matches only completed notes, `Some(false)` only the pending ones.
+    pub done: Option<bool>,
+    /// Matches notes created at or after the given time.
+    pub since: Option<DateTime<Local>>,
+    /// Matches notes created before the given time.
+    pub before: Option<DateTime<Local>>,
+    /// Matches notes that are due before the given time.
+    pub due: Option<DateTime<Local>>,
+    /// Matches notes whose title, body or tags match the search.
+    pub search: Option<Matcher>,
+    /// Matches notes that satisfy the `--query` expression.
+    pub query: Option<Query>,
 }
 
-impl Default for Filter {
-    fn default() -> Self {
-        Self {
-            titles: None,
-            lvl: None,
-            tags: None,
+impl Filter {
+    /// Replaces the id prefixes in the filter with the full ids of the notes they refer to.
+    pub fn resolve_ids(&mut self, notes: &[Note]) -> Result<(), String> {
+        if let Some(ids) = self.ids.as_mut() {
+            for id in ids.iter_mut() {
+                let i = note::find_by_id(notes, id)?;
+                *id = notes[i].id.clone();
+            }
         }
+        Ok(())
     }
-}
 
-impl Filter {
     pub fn is_match(&self, n: &Note) -> bool {
         const OPT: MatchOptions = MatchOptions {
             case_sensitive: false,
@@ -28,6 +53,12 @@ impl Filter {
             require_literal_leading_dot: false,
         };
 
+        if let Some(ids) = &self.ids {
+            if !ids.contains(&n.id) {
+                return false;
+            }
+        }
+
         if let Some(titles) = &self.titles {
             if !titles.iter().any(|p| p.matches_with(&n.title[..], OPT)) {
                 return false;
@@ -49,6 +80,41 @@ impl Filter {
             }
         }
 
+        if let Some(done) = self.done {
+            if n.is_done() != done {
+                return false;
+            }
+        }
+
+        if let Some(since) = self.since {
+            if n.created.is_none_or(|t| t < since) {
+                return false;
+            }
+        }
+        if let Some(before) = self.before {
+            if n.created.is_none_or(|t| t >= before) {
+                return false;
+            }
+        }
+
+        if let Some(due) = self.due {
+            if n.due.is_none_or(|t| t >= due) {
+                return false;
+            }
+        }
+
+        if let Some(m) = &self.search {
+            if !m.is_match(n) {
+                return false;
+            }
+        }
+
+        if let Some(q) = &self.query {
+            if !q.is_match(n) {
+                return false;
+            }
+        }
+
         true
     }
 
@@ -56,10 +122,112 @@ impl Filter {
         matches!(
             self,
             Self {
+                ids: None,
                 titles: None,
                 lvl: None,
-                tags: None
+                tags: None,
+                done: None,
+                since: None,
+                before: None,
+                due: None,
+                search: None,
+                query: None,
             }
         )
     }
 }
+
+/// Returns the search given with the `arg` argument and the `regex`, `fuzzy` and `case-sensitive` flags.
+pub fn search_from_matches(m: &ArgMatches, arg: &str) -> Option<Search> {
+    let mode = if m.is_present("regex") {
+        Mode::Regex
+    } else if m.is_present("fuzzy") {
+        Mode::Fuzzy
+    } else {
+        Mode::Substring
+    };
+    m.value_of(arg).map(|s| Search {
+        pattern: s.to_owned(),
+        mode,
+        case_sensitive: m.is_present("case-sensitive"),
+    })
+}
+
+/// Selects notes for the commands that modify them, such as `remove` and `edit`.
+///
+/// A note is selected if it's in the index range or if it matches the filter.
+#[derive(Debug)]
+pub struct Selector {
+    pub index: Option<Index>,
+    pub filter: Filter,
+    /// The `--query` expression, parsed into the filter when selecting.
+    pub query: Option<String>,
+}
+
+impl Selector {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        let titles = m.values_of("title").map(|i| {
+            i.map(|s| {
+                Pattern::new(s).unwrap_or_else(|e| {
+                    panic!("invalid glob pattern {}: {:?}", s, e);
+                })
+            })
+            .collect::<Vec<_>>()
+        });
+
+        let lvl = m
+            .value_of("lvl")
+            .map(|s| MinMax::parse(s).expect("internal error: MinMax::parse returned none"));
+        let index = m
+            .value_of("index")
+            .map(|s| Index::parse(s).expect("internal error: Index::parse returned none"));
+        let tags = m
+            .values_of("tag")
+            .map(|i| i.map(String::from).collect::<Vec<_>>());
+        let ids = m
+            .values_of("id")
+            .map(|i| i.map(String::from).collect::<Vec<_>>());
+        Self {
+            index,
+            filter: Filter {
+                ids,
+                titles,
+                lvl,
+                tags,
+                done: None,
+                since: None,
+                before: None,
+                due: None,
+                search: None,
+                query: None,
+            },
+            query: m.value_of("query").map(String::from),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_none() && self.filter.is_empty() && self.query.is_none()
+    }
+
+    /// Returns the indices of the selected notes, in order.
+    pub fn select(&mut self, notes: &[Note]) -> Result<Vec<usize>, Box<dyn Error>> {
+        self.filter.resolve_ids(notes)?;
+        self.filter.query = self.query.as_deref().map(str::parse).transpose()?;
+        if let Some(i) = self.index.as_mut() {
+            i.calibrate(notes.len());
+        }
+
+        Ok(notes
+            .iter()
+            .enumerate()
+            .filter(|(i, n)| {
+                let in_range = match &self.index {
+                    Some(idx) => idx.in_range(*i as isize),
+                    None => false,
+                };
+                in_range || (!self.filter.is_empty() && self.filter.is_match(n))
+            })
+            .map(|(i, _)| i)
+            .collect())
+    }
+}
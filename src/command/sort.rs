@@ -0,0 +1,181 @@
+use crate::note::Note;
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortField {
+    Index,
+    Title,
+    Lvl,
+    Tag,
+    Created,
+    Updated,
+    Due,
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+/// Orders `Some` before `None` regardless of the sort direction.
+fn missing_last<T: Ord>(a: Option<T>, b: Option<T>) -> Result<Ordering, Ordering> {
+    match (a, b) {
+        (Some(x), Some(y)) => Ok(x.cmp(&y)),
+        (Some(_), None) => Err(Ordering::Less),
+        (None, Some(_)) => Err(Ordering::Greater),
+        (None, None) => Ok(Ordering::Equal),
+    }
+}
+
+impl SortKey {
+    /// Parses a sort key such as `created` or `-updated`; a leading `-` means descending order.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (descending, name) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let field = match &name.to_lowercase()[..] {
+            "index" => SortField::Index,
+            "title" => SortField::Title,
+            "lvl" | "level" => SortField::Lvl,
+            "tag" | "tags" => SortField::Tag,
+            "created" => SortField::Created,
+            "updated" => SortField::Updated,
+            "due" => SortField::Due,
+            "done" => SortField::Done,
+            _ => return None,
+        };
+        Some(Self { field, descending })
+    }
+
+    /// Compares two notes and their indices.
+    pub fn compare(&self, (i, a): (usize, &Note), (j, b): (usize, &Note)) -> Ordering {
+        let first_tag = |n: &Note| {
+            n.tags
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.to_lowercase())
+                .min()
+        };
+        let ord = match self.field {
+            SortField::Index => Ok(i.cmp(&j)),
+            SortField::Title => Ok(a.title.to_lowercase().cmp(&b.title.to_lowercase())),
+            SortField::Lvl => Ok(a.lvl.unwrap_or_default().cmp(&b.lvl.unwrap_or_default())),
+            // notes without tags, a due date or a done date come last regardless of the direction
+            SortField::Tag => missing_last(first_tag(a), first_tag(b)),
+            SortField::Created => Ok(a.created.cmp(&b.created)),
+            SortField::Updated => Ok(a.updated.cmp(&b.updated)),
+            SortField::Due => missing_last(a.due, b.due),
+            SortField::Done => missing_last(a.done, b.done),
+        };
+        match ord {
+            Ok(o) if self.descending => o.reverse(),
+            Ok(o) | Err(o) => o,
+        }
+    }
+}
+
+/// Compares two notes and their indices by each key in turn.
+pub fn compare(keys: &[SortKey], a: (usize, &Note), b: (usize, &Note)) -> Ordering {
+    keys.iter()
+        .map(|k| k.compare(a, b))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Sorts the notes to print by the keys; they're printed in reverse, so the first one by the keys ends up last.
+///
+/// The notes keep their indices in the todos file, so `todo done -i N` still changes the note listed at N.
+pub fn sort_printed(keys: &[SortKey], notes: &mut [&(usize, Note)]) {
+    notes.sort_by(|(i, a), (j, b)| compare(keys, (*i, a), (*j, b)).reverse());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::date;
+
+    fn keys(s: &[&str]) -> Vec<SortKey> {
+        s.iter().map(|s| SortKey::parse(s).unwrap()).collect()
+    }
+
+    fn note(title: &str, lvl: Option<u8>, due: Option<&str>) -> Note {
+        let mut n = Note::new(title, "");
+        n.lvl = lvl;
+        n.due = due.and_then(|s| date::parse_due(s, crate::note::now()));
+        n
+    }
+
+    fn notes() -> Vec<(usize, Note)> {
+        vec![
+            note("b", Some(1), Some("2030-01-03")),
+            note("c", Some(3), None),
+            note("a", None, Some("2030-01-01")),
+            note("a", Some(3), Some("2030-01-02")),
+            note("d", Some(1), None),
+        ]
+        .into_iter()
+        .enumerate()
+        .collect()
+    }
+
+    /// Returns the indices of the notes in the order of the keys.
+    fn sorted(k: &[&str]) -> Vec<usize> {
+        let notes = notes();
+        let keys = keys(k);
+        let mut refs: Vec<_> = notes.iter().collect();
+        refs.sort_by(|(i, a), (j, b)| compare(&keys, (*i, a), (*j, b)));
+        refs.iter().map(|(i, _)| *i).collect()
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(
+            SortKey::parse("-lvl"),
+            Some(SortKey {
+                field: SortField::Lvl,
+                descending: true
+            })
+        );
+        assert_eq!(SortKey::parse("+Title"), SortKey::parse("title"));
+        assert_eq!(SortKey::parse("level"), SortKey::parse("lvl"));
+        assert_eq!(SortKey::parse("size"), None);
+        assert_eq!(SortKey::parse("--lvl"), None);
+    }
+
+    #[test]
+    fn several_keys_break_ties_in_turn() {
+        assert_eq!(sorted(&["-lvl", "title"]), [3, 1, 0, 4, 2]);
+        assert_eq!(sorted(&["-lvl", "-title"]), [1, 3, 4, 0, 2]);
+        assert_eq!(sorted(&["lvl", "title"]), [2, 0, 4, 3, 1]);
+        assert_eq!(sorted(&["title", "-lvl"]), [3, 2, 0, 1, 4]);
+        // the index decides between notes equal by the other keys
+        assert_eq!(sorted(&["title", "-index"]), [3, 2, 0, 1, 4]);
+        assert_eq!(sorted(&["title", "index"]), [2, 3, 0, 1, 4]);
+    }
+
+    #[test]
+    fn missing_values_come_last_in_both_directions() {
+        assert_eq!(sorted(&["due"]), [2, 3, 0, 1, 4]);
+        assert_eq!(sorted(&["-due"]), [0, 3, 2, 1, 4]);
+        assert_eq!(sorted(&["-due", "-lvl"]), [0, 3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn sorted_notes_keep_their_indices() {
+        let notes = notes();
+        let mut printed: Vec<_> = notes.iter().collect();
+        sort_printed(&keys(&["-lvl", "title"]), &mut printed);
+        // printed in reverse, the first one by the keys comes last
+        let order: Vec<_> = printed.iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, [2, 4, 0, 1, 3]);
+        for (i, n) in printed {
+            assert!(std::ptr::eq(n, &notes[*i].1));
+        }
+    }
+}
@@ -1,15 +1,38 @@
 use serde_derive::{Deserialize, Serialize};
 
 use std::{
+    collections::BTreeMap,
     env,
     error::Error,
-    fs::{self, File},
-    io::Write,
-    path::PathBuf,
+    fs::{self, OpenOptions},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
+pub mod git;
 pub mod hook;
-use hook::Hooks;
+pub mod theme;
+pub mod view;
+use git::Git;
+use hook::{Context, Hooks};
+use theme::Theme;
+use view::View;
+
+use crate::{
+    file::{self, Lock},
+    journal::{self, Entry, Journal},
+    note::Notes,
+};
+
+/// The name of the list kept in the `todos_file`.
+pub const DEFAULT_LIST: &str = "default";
+/// The name of the list kept in the project todos file, the `.todos.toml` closest to the current directory.
+pub const PROJECT_LIST: &str = "project";
+
+const PROJECT_FILE_NAME: &str = ".todos.toml";
+
+const DEFAULT_LOCK_TIMEOUT: f64 = 10.0;
+const DEFAULT_REMOVE_CONFIRM_THRESHOLD: usize = 1;
 
 fn todo_path_default() -> Option<PathBuf> {
     match dirs::home_dir() {
@@ -25,6 +48,29 @@ fn todo_path_env() -> Option<PathBuf> {
     env::var("TODOS_FILE_PATH").map(PathBuf::from).ok()
 }
 
+/// Returns true if the `$TODO_GLOBAL` env variable is set, then the project todos file is not used unless it's selected by name.
+fn global_env() -> bool {
+    env::var("TODO_GLOBAL").is_ok_and(|s| !s.is_empty())
+}
+
+/// Returns the `.todos.toml` closest to the current directory, looking up to but not including the home directory.
+/// `global` is skipped, since it's the default todos file in some setups.
+fn find_project_file(global: &Path) -> Option<PathBuf> {
+    let home = dirs::home_dir();
+    let global = global.canonicalize().ok();
+    let cwd = env::current_dir().ok()?;
+    for dir in cwd.ancestors() {
+        if Some(dir) == home.as_deref() {
+            break;
+        }
+        let p = dir.join(PROJECT_FILE_NAME);
+        if p.is_file() && p.canonicalize().ok() != global {
+            return Some(p);
+        }
+    }
+    None
+}
+
 fn config_dir() -> Result<PathBuf, &'static str> {
     if let Ok(s) = env::var("TODO_CONFIG_DIR") {
         Ok(s.into())
@@ -44,34 +90,90 @@ pub fn config_path_checked() -> Result<PathBuf, Box<dyn Error>> {
     }
     p.push("todo.toml");
     if !p.is_file() {
-        let def = Config::default();
-        let data = toml::to_string_pretty(&def)?;
-        let mut file = File::create(&p)?;
-        file.write_all(data.as_bytes())?;
-        file.sync_all()?;
+        file::save_toml(&p, &Config::default())?;
     }
     Ok(p)
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
+    /// The todos file of the list in use, set to the path of the selected list when the config is loaded.
     pub todos_file: PathBuf,
+    /// The list used unless another one is selected with `--list` or `$TODO_LIST`.
+    pub default_list: Option<String>,
+    /// Use the project todos file, the `.todos.toml` closest to the current directory, instead of the default list.
+    /// Off unless it's set to true.
+    pub project_todos: Option<bool>,
     pub abort_on_hook_error: Option<bool>,
+    /// Keep a copy of the previous version of the todos file next to it.
+    pub backup: Option<bool>,
+    /// How many seconds to wait for other todo processes to finish writing the todos file.
+    pub lock_timeout: Option<f64>,
+    /// `todo remove` asks for confirmation when it would remove more notes than this; 0 means always ask.
+    pub remove_confirm_threshold: Option<usize>,
+    /// The template `todo list` and `todo random` print the notes with, unless `--template` is given.
+    pub list_template: Option<String>,
     pub hooks: Hooks,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub git: Git,
+    /// Named sets of `todo list` arguments.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub views: BTreeMap<String, View>,
+    /// Commands of their own, expanded to the arguments they're defined as, such as `urgent = "list -l 5:"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// Todo lists other than the default one, by name; relative paths are relative to the config directory.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lists: BTreeMap<String, PathBuf>,
+    /// The name of the list in use.
+    #[serde(skip)]
+    pub list: String,
+    /// The `todos_file` as it was in the config file, the path of the default list.
+    #[serde(skip)]
+    default_file: PathBuf,
+    /// The project todos file, if there's one and `project_todos` is enabled.
+    #[serde(skip)]
+    pub project_file: Option<PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            todos_file: todo_path_env().unwrap_or_else(PathBuf::new),
+            todos_file: todo_path_env().unwrap_or_default(),
+            default_list: None,
+            project_todos: Some(false),
             abort_on_hook_error: Some(true),
+            backup: Some(false),
+            lock_timeout: Some(DEFAULT_LOCK_TIMEOUT),
+            remove_confirm_threshold: Some(DEFAULT_REMOVE_CONFIRM_THRESHOLD),
+            list_template: None,
             hooks: Hooks::default(),
+            theme: Theme::default(),
+            git: Git::default(),
+            views: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            lists: BTreeMap::new(),
+            list: DEFAULT_LIST.to_owned(),
+            default_file: PathBuf::new(),
+            project_file: None,
         }
     }
 }
 
 impl Config {
+    /// Loads the config, using the list named in the `$TODO_LIST` env variable if it's set.
     pub fn get() -> Result<Self, Box<dyn Error>> {
+        let list = env::var("TODO_LIST").ok().filter(|s| !s.is_empty());
+        Self::get_list(list.as_deref())
+    }
+
+    /// Loads the config, using the list `name` if it's given.
+    ///
+    /// Otherwise the project todos file is used if `project_todos` is enabled and there's one, unless `$TODO_GLOBAL` or `$TODOS_FILE_PATH` is set;
+    /// then the `default_list`.
+    pub fn get_list(name: Option<&str>) -> Result<Self, Box<dyn Error>> {
         let p = config_path_checked()?;
         let data = fs::read_to_string(&p)?;
         let mut conf: Self = toml::from_str(&data)?;
@@ -80,11 +182,172 @@ impl Config {
         } else if conf.todos_file.as_os_str().is_empty() {
             conf.todos_file= todo_path_default().ok_or("could not determine todos_file path; consider setting the TODOS_FILE_PATH env variable or editing the config file")?;
         }
-
-        // create todos file if it doesn't exist
-        if !conf.todos_file.is_file() {
-            File::create(&conf.todos_file)?;
+        for reserved in &[DEFAULT_LIST, PROJECT_LIST] {
+            if conf.lists.contains_key(*reserved) {
+                return Err(format!(
+                    "the list name {} is reserved, please rename the list in the config file",
+                    reserved
+                )
+                .into());
+            }
         }
+        conf.default_file = conf.todos_file.clone();
+        if conf.project_todos.unwrap_or(false) {
+            conf.project_file = find_project_file(&conf.default_file);
+        }
+
+        let name = match name {
+            Some(name) => name.to_owned(),
+            None if conf.project_file.is_some() && !global_env() && todo_path_env().is_none() => {
+                PROJECT_LIST.to_owned()
+            }
+            None => conf
+                .default_list
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LIST.to_owned()),
+        };
+        conf.todos_file = conf.list_path(&name)?;
+        conf.list = name;
+
+        // create todos file if it doesn't exist, without truncating one another process just made
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&conf.todos_file)?;
         Ok(conf)
     }
+
+    /// Returns the path of the todos file of the list `name`.
+    pub fn list_path(&self, name: &str) -> Result<PathBuf, String> {
+        if name == DEFAULT_LIST {
+            return Ok(self.default_file.clone());
+        }
+        if name == PROJECT_LIST {
+            if !self.project_todos.unwrap_or(false) {
+                return Err(
+                    "project todos files are disabled, set project_todos = true in the config file"
+                        .into(),
+                );
+            }
+            return self.project_file.clone().ok_or_else(|| {
+                format!(
+                    "there's no {} in the current directory or its parents",
+                    PROJECT_FILE_NAME
+                )
+            });
+        }
+        match self.lists.get(name) {
+            Some(p) if p.is_relative() => {
+                let mut dir = config_dir()?;
+                dir.push(p);
+                Ok(dir)
+            }
+            Some(p) => Ok(p.clone()),
+            None => Err(format!(
+                "unknown list {}, the lists are {}",
+                name,
+                self.list_names().join(", ")
+            )),
+        }
+    }
+
+    /// Returns the names of the lists, the default one first, then the project one if there's one.
+    pub fn list_names(&self) -> Vec<&str> {
+        let project = self.project_file.as_ref().map(|_| PROJECT_LIST);
+        std::iter::once(DEFAULT_LIST)
+            .chain(project)
+            .chain(self.lists.keys().map(String::as_str))
+            .collect()
+    }
+
+    /// Returns true if removing `n` notes needs to be confirmed by the user.
+    pub fn should_confirm_remove(&self, n: usize) -> bool {
+        n > self
+            .remove_confirm_threshold
+            .unwrap_or(DEFAULT_REMOVE_CONFIRM_THRESHOLD)
+    }
+
+    /// Locks the todos file for writing, then pulls its repository if configured to.
+    /// The lock should be held from loading the notes until they're saved.
+    pub fn lock(&self) -> Result<Lock, Box<dyn Error>> {
+        let secs = self.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT);
+        let timeout = Duration::try_from_secs_f64(secs)
+            .map_err(|_| format!("invalid lock_timeout in the config file: {}", secs))?;
+        let lock = file::lock(&self.todos_file, timeout).map_err(|e| e.to_string())?;
+        self.git.pull(&self.todos_file)?;
+        Ok(lock)
+    }
+
+    /// Locks the todos files of several lists, such as the two a move changes.
+    ///
+    /// They're always locked in the same order, so two todo processes locking the same files don't wait for each other.
+    pub fn lock_all(lists: &[&Self]) -> Result<Vec<Lock>, Box<dyn Error>> {
+        let mut lists: Vec<_> = lists
+            .iter()
+            .map(|c| {
+                (
+                    fs::canonicalize(&c.todos_file).unwrap_or_else(|_| c.todos_file.clone()),
+                    c,
+                )
+            })
+            .collect();
+        lists.sort_by(|a, b| a.0.cmp(&b.0));
+        lists.dedup_by(|a, b| a.0 == b.0);
+        lists.into_iter().map(|(_, c)| c.lock()).collect()
+    }
+
+    /// Saves the notes to the todos file, backing up the previous version if configured to.
+    pub fn save_notes(&self, notes: &Notes) -> Result<(), Box<dyn Error>> {
+        if let Some(true) = self.backup {
+            file::backup(&self.todos_file)?;
+        }
+        notes.save_to(&self.todos_file)
+    }
+
+    pub fn journal_path(&self) -> PathBuf {
+        journal::journal_path(&self.todos_file)
+    }
+
+    /// Saves the notes and records the operation that changed them in the journal, so it can be undone.
+    pub fn save_change(&self, notes: &Notes, entry: Entry) -> Result<(), Box<dyn Error>> {
+        self.save_notes(notes)?;
+        self.record(entry)
+    }
+
+    /// Records an operation whose notes were saved in the journal, and commits the todos file.
+    pub fn record(&self, entry: Entry) -> Result<(), Box<dyn Error>> {
+        let p = self.journal_path();
+        let mut journal = Journal::load(&p)?;
+        let summary = entry.summary.clone();
+        journal.push(entry);
+        journal.save(&p).map_err(|e| {
+            format!(
+                "the change was saved but could not be recorded in the journal: {}",
+                e
+            )
+        })?;
+        self.commit(&summary)
+    }
+
+    /// Runs the `pre_<command>` hooks; their errors abort the command unless `abort_on_hook_error` is false.
+    /// A hook that exits with an error always aborts the command.
+    pub fn run_pre_hooks(&self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+        match self.hooks.run_pre(ctx) {
+            Ok(None) => (),
+            Ok(Some(reason)) => return Err(reason.into()),
+            Err(e) => match self.abort_on_hook_error {
+                Some(true) | None => return Err(e.into()),
+                Some(false) => println!("pre-{} hook error: {}", ctx.command, e),
+            },
+        }
+        Ok(())
+    }
+
+    /// Commits the todos file with `message` if git's auto_commit is set.
+    pub fn commit(&self, message: &str) -> Result<(), Box<dyn Error>> {
+        self.git
+            .commit(&self.todos_file, message)
+            .map_err(|e| format!("the change was saved but could not be committed: {}", e).into())
+    }
 }
@@ -0,0 +1,127 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::{
+    fs, io,
+    path::Path,
+    process::{Command, Output},
+};
+
+/// Keeping the todos file in a git repository.
+///
+/// git runs in the directory of the todos file, which has to be in a repository already.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Git {
+    /// Commit the todos file after every change, with the summary of the change as the message.
+    pub auto_commit: bool,
+    /// Pull before changing the todos file.
+    pub pull: bool,
+    /// Push after committing a change.
+    pub push: bool,
+    /// The remote to pull from and push to; the upstream of the current branch is used if it's not set.
+    pub remote: Option<String>,
+}
+
+/// Runs git in `dir`, returning its stderr as the error if it fails.
+fn git(dir: &Path, args: &[&str]) -> Result<Output, String> {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => "git is not installed, or not in the PATH".to_owned(),
+            _ => format!("could not run git: {}", e),
+        })?;
+    if out.status.success() {
+        Ok(out)
+    } else {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .find(|s| !s.is_empty())
+            .unwrap_or("no error message");
+        Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            reason
+                .trim_start_matches("fatal: ")
+                .trim_start_matches("error: ")
+        ))
+    }
+}
+
+impl Git {
+    /// Pulls the changes to the repository of the todos file `p`, if it's configured to.
+    pub fn pull(&self, p: &Path) -> Result<(), String> {
+        if !self.pull {
+            return Ok(());
+        }
+        let dir = dir_of(p);
+        let file = file_name(p)?;
+        let mut args = vec!["pull", "--quiet", "--rebase", "--autostash"];
+        let branch;
+        if let Some(remote) = &self.remote {
+            let out = git(dir, &["symbolic-ref", "--short", "HEAD"])?;
+            branch = String::from_utf8_lossy(&out.stdout).trim().to_owned();
+            args.extend(&[remote.as_str(), branch.as_str()]);
+        }
+
+        // the todos file is created empty if it doesn't exist, in a new clone it would keep git from checking out the pulled one
+        let placeholder = fs::metadata(p).map(|m| m.len() == 0).unwrap_or(false)
+            && git(dir, &["ls-files", "--error-unmatch", "--", file]).is_err();
+        if placeholder {
+            fs::remove_file(p).map_err(|e| format!("{}: {}", p.display(), e))?;
+        }
+        let res = git(dir, &args).map(|_| ());
+        if placeholder && !p.exists() {
+            fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(false)
+                .open(p)
+                .map_err(|e| format!("{}: {}", p.display(), e))?;
+        }
+        res
+    }
+
+    /// Commits the file `p` with `message`, and pushes it if configured to.
+    /// Nothing is done if `auto_commit` is not set or the file didn't change.
+    pub fn commit(&self, p: &Path, message: &str) -> Result<(), String> {
+        if !self.auto_commit {
+            return Ok(());
+        }
+        let dir = dir_of(p);
+        let file = file_name(p)?;
+
+        git(dir, &["add", "--", file])?;
+        // diff --quiet fails if there are changes
+        if git(dir, &["diff", "--cached", "--quiet", "--", file]).is_ok() {
+            return Ok(());
+        }
+        git(dir, &["commit", "--quiet", "-m", message, "--", file])?;
+
+        if self.push {
+            let mut args = vec!["push", "--quiet"];
+            if let Some(remote) = &self.remote {
+                args.extend(&[remote.as_str(), "HEAD"]);
+            }
+            git(dir, &args)?;
+        }
+        Ok(())
+    }
+}
+
+fn file_name(p: &Path) -> Result<&str, String> {
+    p.file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| format!("{}: the file name is not valid unicode", p.display()))
+}
+
+/// Returns the directory git runs in for the todos file `p`.
+pub fn dir_of(p: &Path) -> &Path {
+    match p.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
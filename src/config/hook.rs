@@ -1,46 +1,258 @@
+use chrono::{DateTime, Local};
 use serde_derive::{Deserialize, Serialize};
-use std::{io, path::PathBuf, process::Command};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use HookType::*;
+use crate::{
+    format::{self, Record},
+    note::{self, Note},
+};
 
-enum HookType {
-    PreNew,
-    PostNew,
-    PreRemove,
-    PostRemove,
+/// Run after any command that changed the notes.
+pub const ON_CHANGE: &str = "on_change";
+/// Run when a command fails.
+pub const ON_ERROR: &str = "on_error";
+
+/// What the hooks are run for.
+///
+/// It's passed to the hooks in `TODO_*` env variables, as JSON on their stdin
+/// and in the `{name}` placeholders of their `cmd`.
+#[derive(Debug)]
+pub struct Context<'a> {
+    pub command: &'a str,
+    /// The todos file.
+    pub file: &'a Path,
+    /// The name of the list the todos file belongs to.
+    pub list: &'a str,
+    /// The notes the command affects, with their indices; the pre hooks may rewrite them.
+    pub notes: Vec<(usize, Note)>,
+    /// What the JSON a pre hook prints does to the notes.
+    pub rewrite: Rewrite,
+    /// The error the command failed with, for the on_error hooks.
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Hooks {
-    pub pre_new: Option<Vec<Hook>>,
-    pub post_new: Option<Vec<Hook>>,
-    pub pre_remove: Option<Vec<Hook>>,
-    pub post_remove: Option<Vec<Hook>>,
+/// What the JSON a pre hook prints to its stdout does to the notes of the [Context].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rewrite {
+    /// Nothing, it's shown like anything else the hook prints.
+    Nothing,
+    /// It replaces the fields of the pending notes, for the commands that make or edit notes.
+    Fields,
+    /// It narrows the notes down to the ones it contains, by id, for the commands that change the notes as they are.
+    Select,
 }
 
-impl Default for Hooks {
-    fn default() -> Self {
+#[derive(Serialize)]
+struct Input<'a> {
+    command: &'a str,
+    file: &'a Path,
+    list: &'a str,
+    notes: &'a [Record<'a>],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(command: &'a str, file: &'a Path, list: &'a str) -> Self {
         Self {
-            pre_new: None,
-            post_new: None,
-            pre_remove: None,
-            post_remove: None,
+            command,
+            file,
+            list,
+            notes: Vec::new(),
+            rewrite: Rewrite::Nothing,
+            error: None,
+        }
+    }
+
+    /// Sets what the JSON a pre hook prints does to the notes.
+    pub fn with_rewrite(mut self, rewrite: Rewrite) -> Self {
+        self.rewrite = rewrite;
+        self
+    }
+
+    /// Sets the affected notes.
+    pub fn with_notes<'n>(mut self, notes: impl IntoIterator<Item = (usize, &'n Note)>) -> Self {
+        self.notes = notes.into_iter().map(|(i, n)| (i, n.clone())).collect();
+        self
+    }
+
+    fn records(&self) -> Vec<Record<'_>> {
+        self.notes.iter().map(|(i, n)| Record::new(*i, n)).collect()
+    }
+
+    /// Returns the variables of the context by name; the ones about a single note are set only if there's one note.
+    fn vars(&self) -> BTreeMap<&'static str, String> {
+        let mut vars = BTreeMap::new();
+        vars.insert("command", self.command.to_owned());
+        vars.insert("file", self.file.display().to_string());
+        vars.insert("list", self.list.to_owned());
+        vars.insert("count", self.notes.len().to_string());
+        let ids: Vec<_> = self.notes.iter().map(|(_, n)| n.id.as_str()).collect();
+        vars.insert("ids", ids.join(","));
+        if let Some(e) = &self.error {
+            vars.insert("error", e.clone());
+        }
+        if let [r] = &self.records()[..] {
+            vars.insert("index", r.index.to_string());
+            vars.insert("id", r.id.to_owned());
+            vars.insert("title", r.title.to_owned());
+            vars.insert("body", r.body.to_owned());
+            vars.insert("tags", r.tags.join(","));
+            vars.insert("lvl", r.lvl.map(|l| l.to_string()).unwrap_or_default());
+            vars.insert(
+                "due",
+                r.due.as_ref().map(note::format_due).unwrap_or_default(),
+            );
+        }
+        vars
+    }
+
+    fn input(&self) -> Vec<u8> {
+        let input = Input {
+            command: self.command,
+            file: self.file,
+            list: self.list,
+            notes: &self.records(),
+            error: self.error.as_deref(),
+        };
+        let mut data = format::json_line(&input)
+            .expect("internal error: could not serialize the hook input")
+            .into_bytes();
+        data.push(b'\n');
+        data
+    }
+
+    /// Applies `output`, the JSON a pre hook wrote to its stdout, to the notes.
+    fn rewrite(&mut self, output: &str) -> Result<(), String> {
+        match self.rewrite {
+            Rewrite::Nothing => Ok(()),
+            Rewrite::Fields => self.replace_fields(output),
+            Rewrite::Select => self.select(output),
+        }
+    }
+
+    /// Keeps only the notes whose ids are in `output`.
+    fn select(&mut self, output: &str) -> Result<(), String> {
+        let output: Selection = serde_json::from_str(output).map_err(|e| e.to_string())?;
+        if let Some(s) = output
+            .notes
+            .iter()
+            .find(|s| !self.notes.iter().any(|(_, n)| n.id == s.id))
+        {
+            return Err(format!(
+                "it returned the note {}, which isn't a pending one",
+                s.id
+            ));
+        }
+        self.notes
+            .retain(|(_, n)| output.notes.iter().any(|s| s.id == n.id));
+        Ok(())
+    }
+
+    /// Replaces the fields of the notes with the ones in `output`.
+    fn replace_fields(&mut self, output: &str) -> Result<(), String> {
+        let output: Output = serde_json::from_str(output).map_err(|e| e.to_string())?;
+        if output.notes.len() != self.notes.len() {
+            return Err(format!(
+                "it returned {} notes instead of {}",
+                output.notes.len(),
+                self.notes.len()
+            ));
+        }
+        if output.notes.iter().any(|p| p.title.trim().is_empty()) {
+            return Err("it returned a note with no title".to_owned());
+        }
+        for ((_, n), p) in self.notes.iter_mut().zip(output.notes) {
+            n.title = p.title;
+            n.body = p.body;
+            n.tags = Some(p.tags).filter(|t| !t.is_empty());
+            n.lvl = p.lvl;
+            n.due = p.due;
+        }
+        Ok(())
+    }
+}
+
+/// The notes a pre hook writes to its stdout to replace the pending ones, in the same order.
+#[derive(Deserialize)]
+struct Output {
+    notes: Vec<Pending>,
+}
+
+/// The notes a pre hook writes to its stdout to keep only those of the pending ones.
+#[derive(Deserialize)]
+struct Selection {
+    notes: Vec<Selected>,
+}
+
+/// A note to keep, only its id is read.
+#[derive(Deserialize)]
+struct Selected {
+    id: String,
+}
+
+/// The fields of a note a pre hook can change, the other ones are ignored.
+#[derive(Deserialize)]
+struct Pending {
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    lvl: Option<u8>,
+    due: Option<DateTime<Local>>,
+}
+
+/// The names of the placeholders, which are the names of the variables of a [Context] with a single note.
+const PLACEHOLDERS: &[&str] = &[
+    "command", "file", "list", "count", "ids", "error", "index", "id", "title", "body", "tags",
+    "lvl", "due",
+];
+
+/// Replaces the placeholders in `arg`, such as `{title}`, with the variables they name.
+/// Anything else in braces is left as is.
+fn expand(arg: &str, vars: &BTreeMap<&'static str, String>) -> String {
+    let mut out = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let name = rest
+            .find('}')
+            .map(|end| &rest[..end])
+            .filter(|name| PLACEHOLDERS.contains(name));
+        match name {
+            Some(name) => {
+                out.push_str(vars.get(name).map(String::as_str).unwrap_or_default());
+                rest = &rest[name.len() + 1..];
+            }
+            None => out.push('{'),
         }
     }
+    out.push_str(rest);
+    out
 }
 
+/// The hooks by the event they run on: `pre_<command>` and `post_<command>` for every command,
+/// `on_change` and `on_error`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Hooks(BTreeMap<String, Vec<Hook>>);
+
 impl Hooks {
-    fn run(&self, hook: HookType) -> io::Result<()> {
-        let hooks = match hook {
-            PreNew => self.pre_new.as_ref(),
-            PostNew => self.post_new.as_ref(),
-            PreRemove => self.pre_remove.as_ref(),
-            PostRemove => self.post_remove.as_ref(),
-        };
-        if let Some(hks) = hooks {
-            let mut last_err: io::Result<()> = Ok(());
+    fn run(&self, event: &str, ctx: &Context) -> Result<(), String> {
+        if let Some(hks) = self.0.get(event) {
+            let mut last_err = Ok(());
             for h in hks {
-                if let Err(e) = h.run() {
+                if let Err(e) = h.run(ctx) {
                     last_err = match h.abort_on_error {
                         Some(true) | None => return Err(e),
                         Some(false) => Err(e),
@@ -53,46 +265,572 @@ impl Hooks {
         }
     }
 
-    pub fn run_pre_new(&self) -> io::Result<()> {
-        self.run(PreNew)
+    /// Runs the hooks before the command takes effect, one after the other, letting them rewrite the notes of `ctx`.
+    /// Returns why the command has to be aborted if a hook exited with an error;
+    /// the hooks after it still run if it has `abort_on_error = false`.
+    pub fn run_pre(&self, ctx: &mut Context) -> Result<Option<String>, String> {
+        let event = format!("pre_{}", ctx.command);
+        let mut veto = None;
+        let mut last_err = Ok(());
+        for h in self.0.get(&event).into_iter().flatten() {
+            let abort = h.abort_on_error != Some(false);
+            match h.filter(ctx) {
+                Ok(Some(reason)) => {
+                    let reason = format!("aborted by a {} hook: {}", event, reason);
+                    if abort {
+                        return Ok(Some(veto.unwrap_or(reason)));
+                    }
+                    veto = veto.or(Some(reason));
+                }
+                Ok(None) => (),
+                // a hook that exited with an error aborts the command, whatever happened after it
+                Err(e) if abort => return veto.map_or(Err(e), |r| Ok(Some(r))),
+                Err(e) => last_err = Err(e),
+            }
+        }
+        match veto {
+            Some(r) => Ok(Some(r)),
+            None => last_err.map(|_| None),
+        }
     }
 
-    pub fn run_post_new(&self) -> io::Result<()> {
-        self.run(PostNew)
+    /// Runs the hooks after the command took effect.
+    pub fn run_post(&self, ctx: &Context) -> Result<(), String> {
+        self.run(&format!("post_{}", ctx.command), ctx)
     }
-    pub fn run_pre_remove(&self) -> io::Result<()> {
-        self.run(PreRemove)
+
+    pub fn run_on_change(&self, ctx: &Context) -> Result<(), String> {
+        self.run(ON_CHANGE, ctx)
+    }
+
+    pub fn run_on_error(&self, ctx: &Context) -> Result<(), String> {
+        self.run(ON_ERROR, ctx)
     }
 
-    pub fn run_post_remove(&self) -> io::Result<()> {
-        self.run(PostRemove)
+    /// Returns a warning for each event that isn't one, given the names of the commands; their hooks never run.
+    ///
+    /// The hooks themselves are checked when they're run, so a mistake in the config file doesn't break
+    /// the commands needed to find and fix it.
+    pub fn warnings(&self, commands: &[&str]) -> Vec<String> {
+        let known = |event: &str| {
+            event == ON_CHANGE
+                || event == ON_ERROR
+                || event
+                    .strip_prefix("pre_")
+                    .or_else(|| event.strip_prefix("post_"))
+                    .is_some_and(|cmd| commands.contains(&cmd))
+        };
+        self.0
+            .keys()
+            .filter(|event| !known(event))
+            .map(|event| {
+                format!(
+                    "unknown hook {} in the config file is ignored, the hooks are pre_COMMAND, post_COMMAND, on_change and on_error",
+                    event
+                )
+            })
+            .collect()
+    }
+}
+
+/// The command of a hook: a command and its arguments, or a command line for `sh -c` if `shell` is set.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Cmd {
+    Args(Vec<String>),
+    Line(String),
+}
+
+impl fmt::Display for Cmd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Args(args) => write!(f, "{}", args.join(" ")),
+            Self::Line(line) => write!(f, "{}", line),
+        }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Hook {
-    cmd: Vec<String>,
+    cmd: Cmd,
     silent: Option<bool>,
     working_dir: Option<PathBuf>,
+    /// Stop running the other hooks of the event if this one fails; the command fails either way.
     abort_on_error: Option<bool>,
+    /// How many seconds the hook may run before it's killed and fails.
+    timeout: Option<f64>,
+    /// Environment variables set for the hook, on top of the `TODO_*` ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    /// Run `cmd`, a string, with `sh -c`.
+    shell: Option<bool>,
+    /// Start the hook without waiting for it, so its exit code is ignored.
+    r#async: Option<bool>,
+}
+
+/// Kills the hook and the commands it started, which are in its process group.
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    // SAFETY: killpg only sends a signal, the group is the one the hook was started in
+    let res = unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) };
+    if res != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Reads `r` to the end on a thread.
+fn read_all<R: Read + Send + 'static>(r: Option<R>) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        if let Some(mut r) = r {
+            r.read_to_end(&mut data)?;
+        }
+        Ok(data)
+    })
 }
 
 impl Hook {
-    fn run(&self) -> io::Result<()> {
-        if self.cmd.is_empty() {
-            return Ok(());
+    fn check(&self) -> Result<(), String> {
+        match (&self.cmd, self.shell) {
+            (Cmd::Args(_), Some(true)) => {
+                return Err("cmd has to be a string when shell is true".to_owned())
+            }
+            (Cmd::Line(_), Some(false) | None) => {
+                return Err("cmd has to be an array unless shell is true".to_owned())
+            }
+            _ => (),
         }
-        let mut cmd = Command::new(&self.cmd[0]);
-        for arg in &self.cmd[1..] {
-            cmd.arg(arg);
+        self.timeout().map(|_| ())
+    }
+
+    fn check_config(&self) -> Result<(), String> {
+        self.check()
+            .map_err(|e| format!("invalid hook `{}` in the config file: {}", self.cmd, e))
+    }
+
+    fn timeout(&self) -> Result<Option<Duration>, String> {
+        match self.timeout {
+            Some(secs) if secs > 0.0 => Duration::try_from_secs_f64(secs)
+                .map(Some)
+                .map_err(|_| format!("invalid timeout {}", secs)),
+            Some(secs) => Err(format!("invalid timeout {}", secs)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the command to run for `ctx`, or None if `cmd` is empty.
+    ///
+    /// The placeholders aren't replaced in a command line for `sh -c`,
+    /// where the values could be taken for shell syntax; it can use the env variables instead.
+    fn command(&self, ctx: &Context) -> Option<Command> {
+        let vars = ctx.vars();
+        let mut cmd = match &self.cmd {
+            Cmd::Args(args) => {
+                let (name, args) = args.split_first()?;
+                let mut cmd = Command::new(expand(name, &vars));
+                for arg in args {
+                    cmd.arg(expand(arg, &vars));
+                }
+                cmd
+            }
+            Cmd::Line(line) if line.trim().is_empty() => return None,
+            Cmd::Line(line) => {
+                let mut cmd = Command::new("sh");
+                cmd.arg("-c").arg(line);
+                cmd
+            }
+        };
+        for (k, v) in &vars {
+            cmd.env(format!("TODO_{}", k.to_uppercase()), v);
         }
+        cmd.envs(&self.env);
         if let Some(wd) = &self.working_dir {
             cmd.current_dir(wd);
         }
+        // a hook that can time out gets its own process group, so the commands it started are killed with it
+        #[cfg(unix)]
+        if self.timeout.is_some() && self.r#async != Some(true) {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        Some(cmd)
+    }
+
+    /// Starts `cmd` and writes the input of `ctx` to its stdin.
+    ///
+    /// The input is written on a thread, so a hook that prints a lot before reading it can't block.
+    fn spawn(
+        &self,
+        cmd: &mut Command,
+        ctx: &Context,
+    ) -> Result<(Child, JoinHandle<io::Result<()>>), String> {
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not run the hook `{}`: {}", self.cmd, e))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .expect("internal error: the stdin of the hook is not piped");
+        let input = ctx.input();
+        let writer = thread::spawn(move || match stdin.write_all(&input) {
+            // a hook that doesn't read its stdin may exit before it's written
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+            _ => Ok(()),
+        });
+        Ok((child, writer))
+    }
+
+    /// Waits for the hook to exit, killing it if it runs for longer than its timeout.
+    fn wait(&self, child: &mut Child) -> Result<ExitStatus, String> {
+        let err = |e: io::Error| format!("could not wait for the hook `{}`: {}", self.cmd, e);
+        let timeout = match self.timeout()? {
+            Some(t) => t,
+            None => return child.wait().map_err(err),
+        };
+        let start = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait().map_err(err)? {
+                return Ok(status);
+            }
+            if start.elapsed() >= timeout {
+                kill(child);
+                let _ = child.wait();
+                return Err(format!(
+                    "the hook `{}` was killed after running for {} seconds",
+                    self.cmd,
+                    timeout.as_secs_f32()
+                ));
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Runs the hook and waits for it to exit, unless it's async.
+    fn run(&self, ctx: &Context) -> Result<(), String> {
+        self.check_config()?;
+        let mut cmd = match self.command(ctx) {
+            Some(cmd) => cmd,
+            None => return Ok(()),
+        };
         if let Some(true) = self.silent {
-            cmd.output().map(|_| ())
-        } else {
-            cmd.spawn().map(|_| ())
+            cmd.stdout(Stdio::null()).stderr(Stdio::null());
+        }
+        let (mut child, writer) = self.spawn(&mut cmd, ctx)?;
+        if let Some(true) = self.r#async {
+            return Ok(());
+        }
+
+        let status = self.wait(&mut child)?;
+        writer
+            .join()
+            .expect("internal error: the thread writing the hook input panicked")
+            .map_err(|e| format!("could not write to the hook `{}`: {}", self.cmd, e))?;
+        if !status.success() {
+            return Err(format!("the hook `{}` failed: {}", self.cmd, status));
+        }
+        Ok(())
+    }
+
+    /// Runs the hook as a filter of the notes of `ctx`, waiting for it to exit; an async hook is only started.
+    ///
+    /// If it prints a JSON object and the command lets it, the notes in it rewrite the ones of `ctx`;
+    /// anything else it prints is shown.
+    /// Returns its stderr, or its exit status if its stderr is empty, if it exited with an error.
+    fn filter(&self, ctx: &mut Context) -> Result<Option<String>, String> {
+        self.check_config()?;
+        if let Some(true) = self.r#async {
+            return self.run(ctx).map(|_| None);
+        }
+        let mut cmd = match self.command(ctx) {
+            Some(cmd) => cmd,
+            None => return Ok(None),
+        };
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let (mut child, writer) = self.spawn(&mut cmd, ctx)?;
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+
+        let status = self.wait(&mut child)?;
+        let io_err = |e: io::Error| format!("could not talk to the hook `{}`: {}", self.cmd, e);
+        let join = "internal error: a thread talking to the hook panicked";
+        writer.join().expect(join).map_err(io_err)?;
+        let stdout = stdout.join().expect(join).map_err(io_err)?;
+        let stderr = stderr.join().expect(join).map_err(io_err)?;
+
+        let silent = self.silent == Some(true);
+        let stderr = String::from_utf8_lossy(&stderr);
+        if !status.success() {
+            return Ok(Some(match stderr.trim() {
+                "" => status.to_string(),
+                reason => reason.to_owned(),
+            }));
+        }
+        if !silent {
+            eprint!("{}", stderr);
+        }
+
+        let stdout = String::from_utf8_lossy(&stdout);
+        if ctx.rewrite != Rewrite::Nothing && stdout.trim_start().starts_with('{') {
+            ctx.rewrite(&stdout)
+                .map_err(|e| format!("invalid output of the hook `{}`: {}", self.cmd, e))?;
+        } else if !silent {
+            print!("{}", stdout);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, fs};
+
+    fn hook(toml: &str) -> Hook {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn ctx() -> Context<'static> {
+        Context::new("new", Path::new("todos.toml"), "default")
+    }
+
+    /// Returns an empty directory for the test `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("todo-hook-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn notes(titles: &[&str]) -> Vec<Note> {
+        titles
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let mut n = Note::new(t, "");
+                n.id = format!("{:08x}", i);
+                n
+            })
+            .collect()
+    }
+
+    #[test]
+    fn select_keeps_the_returned_ids() {
+        let notes = notes(&["a", "b", "c"]);
+        let mut ctx = Context::new("remove", Path::new("todos.toml"), "default")
+            .with_notes(notes.iter().enumerate())
+            .with_rewrite(Rewrite::Select);
+        ctx.rewrite(r#"{"notes": [{"id": "00000002", "title": "c"}, {"id": "00000000"}]}"#)
+            .unwrap();
+        let ids: Vec<_> = ctx.notes.iter().map(|(i, n)| (*i, n.id.as_str())).collect();
+        assert_eq!(ids, [(0, "00000000"), (2, "00000002")]);
+    }
+
+    #[test]
+    fn select_rejects_other_notes() {
+        let notes = notes(&["a", "b"]);
+        let mut ctx = Context::new("done", Path::new("todos.toml"), "default")
+            .with_notes(notes.iter().enumerate().take(1))
+            .with_rewrite(Rewrite::Select);
+        assert!(ctx.rewrite(r#"{"notes": [{"id": "00000001"}]}"#).is_err());
+        assert_eq!(ctx.notes.len(), 1);
+    }
+
+    #[test]
+    fn non_zero_exit_fails_the_hook() {
+        let h = hook(r#"cmd = ["sh", "-c", "exit 3"]"#);
+        let err = h.run(&ctx()).unwrap_err();
+        assert!(err.contains("failed"), "{}", err);
+
+        // a pre hook's stderr is the reason the command is aborted
+        let h = hook(r#"cmd = ["sh", "-c", "echo no tags >&2; exit 1"]"#);
+        assert_eq!(h.filter(&mut ctx()).unwrap().as_deref(), Some("no tags"));
+    }
+
+    #[test]
+    fn timeout_kills_the_hook() {
+        let h = hook(
+            r#"cmd = ["sleep", "10"]
+timeout = 0.2"#,
+        );
+        let start = Instant::now();
+        let err = h.run(&ctx()).unwrap_err();
+        assert!(err.contains("killed"), "{}", err);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(h.filter(&mut ctx()).unwrap_err().contains("killed"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout_kills_the_commands_a_shell_hook_started() {
+        let dir = test_dir("timeout-group");
+        let h = hook(&format!(
+            r#"cmd = "sleep 10 & echo $! > pid; wait"
+shell = true
+timeout = 1
+working_dir = {:?}"#,
+            dir
+        ));
+        let start = Instant::now();
+        let err = h.filter(&mut ctx()).unwrap_err();
+        assert!(err.contains("killed"), "{}", err);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // the sleep the shell started is gone too
+        let pid = fs::read_to_string(dir.join("pid")).unwrap();
+        let alive = || {
+            Command::new("kill")
+                .args(["-0", pid.trim()])
+                .stderr(Stdio::null())
+                .status()
+                .unwrap()
+                .success()
+        };
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while alive() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
         }
+        assert!(!alive(), "the sleep {} is still running", pid.trim());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn env_reaches_the_hook() {
+        let dir = test_dir("env");
+        let h = hook(&format!(
+            r#"cmd = ["sh", "-c", "printf '%s %s' \"$GREETING\" \"$TODO_COMMAND\" > out"]
+working_dir = {:?}
+[env]
+GREETING = "hello""#,
+            dir
+        ));
+        h.run(&ctx()).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("out")).unwrap(), "hello new");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shell_runs_the_line_with_sh() {
+        let dir = test_dir("shell");
+        let h = hook(&format!(
+            r#"cmd = "printf a | tr a b > out && printf '%s' \"$TODO_LIST\" >> out"
+shell = true
+working_dir = {:?}"#,
+            dir
+        ));
+        h.run(&ctx()).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("out")).unwrap(), "bdefault");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn async_hook_is_not_waited_on() {
+        let h = hook(
+            r#"cmd = ["sh", "-c", "sleep 3; exit 1"]
+async = true"#,
+        );
+        let start = Instant::now();
+        h.run(&ctx()).unwrap();
+        assert_eq!(h.filter(&mut ctx()).unwrap(), None);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn check_rejects_a_cmd_that_does_not_match_shell() {
+        assert!(hook(r#"cmd = "echo hi""#).check().is_err());
+        assert!(hook(
+            r#"cmd = "echo hi"
+shell = false"#
+        )
+        .check()
+        .is_err());
+        assert!(hook(
+            r#"cmd = ["echo", "hi"]
+shell = true"#
+        )
+        .check()
+        .is_err());
+        assert!(hook(
+            r#"cmd = "echo hi"
+shell = true"#
+        )
+        .check()
+        .is_ok());
+        assert!(hook(r#"cmd = ["echo", "hi"]"#).check().is_ok());
+    }
+
+    #[test]
+    fn check_rejects_an_invalid_timeout() {
+        for t in &["0", "-1", "1e300"] {
+            let h = hook(&format!("cmd = [\"true\"]\ntimeout = {}", t));
+            assert!(h.check().is_err(), "timeout {} was accepted", t);
+        }
+        assert!(hook("cmd = [\"true\"]\ntimeout = 0.5").check().is_ok());
+    }
+
+    #[test]
+    fn failed_pre_hook_without_abort_on_error_lets_the_others_run() {
+        let dir = test_dir("pre-abort");
+        let hooks = |abort: bool| -> Hooks {
+            toml::from_str(&format!(
+                r#"[[pre_new]]
+cmd = ["sh", "-c", "echo refused >&2; exit 1"]
+abort_on_error = {}
+[[pre_new]]
+cmd = ["touch", "ran"]
+working_dir = {:?}
+[[pre_new]]
+cmd = ["sh", "-c", "echo again >&2; exit 1"]"#,
+                abort, dir
+            ))
+            .unwrap()
+        };
+
+        let reason = hooks(true).run_pre(&mut ctx()).unwrap();
+        assert_eq!(
+            reason.as_deref(),
+            Some("aborted by a pre_new hook: refused")
+        );
+        assert!(!dir.join("ran").exists());
+
+        // the command is still aborted, with the reason of the first hook that refused it
+        let reason = hooks(false).run_pre(&mut ctx()).unwrap();
+        assert_eq!(
+            reason.as_deref(),
+            Some("aborted by a pre_new hook: refused")
+        );
+        assert!(dir.join("ran").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_hooks_are_warnings_and_invalid_ones_fail_when_run() {
+        let hooks: Hooks = toml::from_str(
+            r#"[[pre_nwe]]
+cmd = ["true"]
+[[pre_new]]
+cmd = "echo hi"
+[[post_list]]
+cmd = ["true"]"#,
+        )
+        .unwrap();
+        let warnings = hooks.warnings(&["new", "list"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].starts_with("unknown hook pre_nwe"),
+            "{}",
+            warnings[0]
+        );
+
+        let err = hooks.run_pre(&mut ctx()).unwrap_err();
+        assert!(err.starts_with("invalid hook `echo hi`"), "{}", err);
+        let list = Context::new("list", Path::new("todos.toml"), "default");
+        assert_eq!(hooks.run_post(&list), Ok(()));
     }
 }
@@ -0,0 +1,48 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The colors notes are displayed with.
+///
+/// A color is a space separated list of attributes (bold, dimmed, italic, underline, reverse)
+/// and colors (black, red, green, yellow, blue, magenta, cyan, white), optionally prefixed
+/// with `bright_` or, for the background, with `on_`; such as "bold bright_red on_black".
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+    pub index: String,
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    /// The due date and the other details after the body.
+    pub status: String,
+    pub overdue: String,
+    /// Used for the whole note if it's done.
+    pub done: String,
+    /// The parts of the notes that matched a search, on top of their usual color.
+    pub highlight: String,
+    /// The title colors by importance level; the keys are the lowest levels the colors apply to.
+    pub lvl: BTreeMap<String, String>,
+    /// The title colors by tag, used if the note has no level color.
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let lvl = [("3", "yellow"), ("5", "bold red")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Self {
+            index: String::new(),
+            id: "dimmed".to_owned(),
+            title: String::new(),
+            body: String::new(),
+            status: "dimmed".to_owned(),
+            overdue: "bold red".to_owned(),
+            done: "dimmed".to_owned(),
+            highlight: "bold underline".to_owned(),
+            lvl,
+            tags: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// A named set of `todo list` arguments, run with `todo view NAME` or `todo list @NAME`.
+///
+/// Every field is the same as the `todo list` argument of the same name.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct View {
+    /// Displayed next to the name by `todo view`.
+    pub description: Option<String>,
+    /// Glob patterns the title must match.
+    pub title: Vec<String>,
+    pub index: Option<String>,
+    pub id: Vec<String>,
+    pub lvl: Option<String>,
+    pub tag: Vec<String>,
+    pub done: bool,
+    pub all: bool,
+    pub since: Option<String>,
+    pub before: Option<String>,
+    pub overdue: bool,
+    pub due_within: Option<String>,
+    pub sort: Vec<String>,
+    pub search: Option<String>,
+    pub query: Option<String>,
+    pub time: bool,
+    pub format: Option<String>,
+    pub template: Option<String>,
+}
+
+impl View {
+    /// Returns the view as `todo list` arguments, followed by `extra`, which override the ones of the view.
+    pub fn args(&self, extra: &[String]) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut opt = |name: &str, val: &Option<String>| {
+            if let Some(s) = val {
+                // --name=value, so values starting with - aren't taken for flags
+                args.push(format!("--{}={}", name, s));
+            }
+        };
+        opt("index", &self.index);
+        opt("level", &self.lvl);
+        opt("since", &self.since);
+        opt("before", &self.before);
+        opt("due-within", &self.due_within);
+        opt("search", &self.search);
+        opt("query", &self.query);
+        opt("format", &self.format);
+        opt("template", &self.template);
+
+        for (name, vals) in &[("id", &self.id), ("tag", &self.tag), ("sort", &self.sort)] {
+            if !vals.is_empty() {
+                args.push(format!("--{}={}", name, vals.join(",")));
+            }
+        }
+        for (name, set) in &[
+            ("done", self.done),
+            ("all", self.all),
+            ("overdue", self.overdue),
+            ("time", self.time),
+        ] {
+            if *set {
+                args.push(format!("--{}", name));
+            }
+        }
+
+        args.extend(extra.iter().cloned());
+        // the titles go last, so they don't end the options given in `extra`
+        if !self.title.is_empty() {
+            if !extra.iter().any(|s| s == "--") {
+                args.push("--".to_owned());
+            }
+            args.extend(self.title.iter().cloned());
+        }
+        args
+    }
+}
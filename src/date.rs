@@ -0,0 +1,241 @@
+// This file is also included by the build script, it should only depend on chrono and std.
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+    Weekday,
+};
+
+/// Parses a duration such as `30m`, `5h`, `3d` or `2w`.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let i = s.find(|c: char| !c.is_ascii_digit())?;
+    let n = s[..i].parse::<i64>().ok()?;
+    match &s[i..] {
+        "s" => Duration::try_seconds(n),
+        "m" | "min" => Duration::try_minutes(n),
+        "h" => Duration::try_hours(n),
+        "d" => Duration::try_days(n),
+        "w" => Duration::try_weeks(n),
+        _ => None,
+    }
+}
+
+fn local(dt: NaiveDateTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&dt).earliest()
+}
+
+fn start_of_day(d: NaiveDate) -> Option<DateTime<Local>> {
+    local(d.and_time(NaiveTime::from_hms_opt(0, 0, 0)?))
+}
+
+fn end_of_day(d: NaiveDate) -> Option<DateTime<Local>> {
+    local(d.and_time(NaiveTime::from_hms_opt(23, 59, 59)?))
+}
+
+/// Returns true if the time is the end of a day, as is the case for due dates given without a time.
+pub fn is_end_of_day(t: &DateTime<Local>) -> bool {
+    t.hour() == 23 && t.minute() == 59 && t.second() == 59
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parses an absolute date or time.
+///
+/// Accepted formats are RFC 3339, `YYYY-MM-DD HH:MM[:SS]`, `YYYY-MM-DD`,
+/// `today` and `yesterday`.
+/// Dates without a time refer to the start of the day.
+pub fn parse_date(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let s = s.trim();
+    match &s.to_lowercase()[..] {
+        "now" => return Some(now),
+        "today" => return start_of_day(now.date_naive()),
+        "yesterday" => return start_of_day(now.date_naive().pred_opt()?),
+        _ => (),
+    };
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Local));
+    }
+    for f in &[
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, f) {
+            return local(dt);
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(start_of_day)
+}
+
+/// Parses a point in the past, either as an absolute date or as a duration relative to now.
+///
+/// For example `3d` means 3 days ago.
+pub fn parse_past(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    match parse_duration(s) {
+        Some(d) => now.checked_sub_signed(d),
+        None => parse_date(s, now),
+    }
+}
+
+/// Parses a due date.
+///
+/// Accepted values are the absolute dates accepted by [parse_date], `tomorrow`,
+/// weekday names such as `fri` or `friday` (the next such day after today)
+/// and durations relative to now such as `+3d`.
+/// Dates without a time refer to the end of the day.
+pub fn parse_due(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let s = s.trim();
+    if let Some(d) = parse_duration(s.strip_prefix('+').unwrap_or(s)) {
+        return now.checked_add_signed(d);
+    }
+
+    let today = now.date_naive();
+    let lower = s.to_lowercase();
+    match &lower[..] {
+        "today" => return end_of_day(today),
+        "tomorrow" => return end_of_day(today.succ_opt()?),
+        _ => (),
+    };
+    if let Some(day) = parse_weekday(&lower) {
+        let from = today.weekday().num_days_from_monday();
+        let to = day.num_days_from_monday();
+        let days = (to + 7 - from) % 7;
+        let days = if days == 0 { 7 } else { days };
+        return end_of_day(today.checked_add_signed(Duration::try_days(i64::from(days))?)?);
+    }
+
+    match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        Ok(d) => end_of_day(d),
+        Err(_) => parse_date(s, now),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    /// A Friday afternoon.
+    fn now() -> DateTime<Local> {
+        at(2024, 5, 17, 15, 30, 0)
+    }
+
+    #[test]
+    fn due_today_and_tomorrow_are_the_end_of_the_day() {
+        assert_eq!(parse_due("today", now()), Some(at(2024, 5, 17, 23, 59, 59)));
+        assert_eq!(
+            parse_due("Tomorrow", now()),
+            Some(at(2024, 5, 18, 23, 59, 59))
+        );
+        assert!(is_end_of_day(&parse_due("today", now()).unwrap()));
+    }
+
+    #[test]
+    fn due_weekdays_are_after_today() {
+        assert_eq!(now().weekday(), Weekday::Fri);
+        assert_eq!(parse_due("fri", now()), Some(at(2024, 5, 24, 23, 59, 59)));
+        assert_eq!(
+            parse_due("friday", now()),
+            Some(at(2024, 5, 24, 23, 59, 59))
+        );
+        assert_eq!(parse_due("sat", now()), Some(at(2024, 5, 18, 23, 59, 59)));
+        assert_eq!(parse_due("Mon", now()), Some(at(2024, 5, 20, 23, 59, 59)));
+        assert_eq!(parse_due("thurs", now()), Some(at(2024, 5, 23, 23, 59, 59)));
+    }
+
+    #[test]
+    fn due_durations_are_relative_to_now() {
+        assert_eq!(parse_due("+3d", now()), Some(at(2024, 5, 20, 15, 30, 0)));
+        assert_eq!(parse_due("+12h", now()), Some(at(2024, 5, 18, 3, 30, 0)));
+        assert_eq!(parse_due("+2w", now()), Some(at(2024, 5, 31, 15, 30, 0)));
+        assert_eq!(parse_due("+30m", now()), Some(at(2024, 5, 17, 16, 0, 0)));
+        assert_eq!(parse_due("3d", now()), parse_due("+3d", now()));
+    }
+
+    #[test]
+    fn due_dates() {
+        assert_eq!(
+            parse_due("2024-06-01", now()),
+            Some(at(2024, 6, 1, 23, 59, 59))
+        );
+        assert_eq!(
+            parse_due("2024-06-01 09:15", now()),
+            Some(at(2024, 6, 1, 9, 15, 0))
+        );
+        assert_eq!(
+            parse_due("2024-06-01T09:15:30", now()),
+            Some(at(2024, 6, 1, 9, 15, 30))
+        );
+        assert!(!is_end_of_day(
+            &parse_due("2024-06-01 09:15", now()).unwrap()
+        ));
+    }
+
+    #[test]
+    fn past_dates_are_the_start_of_the_day() {
+        assert_eq!(parse_date("today", now()), Some(at(2024, 5, 17, 0, 0, 0)));
+        assert_eq!(
+            parse_date("yesterday", now()),
+            Some(at(2024, 5, 16, 0, 0, 0))
+        );
+        assert_eq!(parse_date("now", now()), Some(now()));
+        assert_eq!(
+            parse_date("2024-06-01", now()),
+            Some(at(2024, 6, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            parse_date("2024-06-01 09:15", now()),
+            Some(at(2024, 6, 1, 9, 15, 0))
+        );
+        assert_eq!(parse_past("3d", now()), Some(at(2024, 5, 14, 15, 30, 0)));
+        assert_eq!(
+            parse_past("2024-05-01", now()),
+            Some(at(2024, 5, 1, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        for s in &[
+            "",
+            "someday",
+            "+3x",
+            "+d",
+            "-3d",
+            "3 d",
+            "fri day",
+            "2024-13-01",
+            "2024-02-30",
+            "2024-06-01 25:00",
+            "01/06/2024",
+        ] {
+            assert_eq!(parse_due(s, now()), None, "{:?}", s);
+            assert_eq!(parse_date(s, now()), None, "{:?}", s);
+            assert_eq!(parse_past(s, now()), None, "{:?}", s);
+        }
+        // these are due dates only
+        for s in &["tomorrow", "fri", "+3d"] {
+            assert_eq!(parse_past(s, now()), None, "{:?}", s);
+        }
+        assert_eq!(parse_duration("3y"), None);
+        assert_eq!(parse_duration("99999999999999w"), None);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    date,
+    note::{self, Note},
+};
+
+use std::{env, error::Error, fs, path::PathBuf, process::Command};
+
+const HELP: &str = "\
+# The first line is the title of the note.
+# The lines after the title, up to the first empty line, are headers:
+#   tags: comma separated list of tags
+#   lvl: importance level (0..255)
+#   due: due date, such as 2026-11-01, 2026-11-01 15:30, tomorrow, fri or +3d
+# Everything after the first empty line is the body.
+# These help lines are ignored, and so are the header lines starting with '#'.
+# Leave the file (or the title and the body) empty to abort.
+";
+
+fn editor() -> Vec<String> {
+    let cmd = env::var("VISUAL")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| env::var("EDITOR").ok().filter(|s| !s.trim().is_empty()))
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                String::from("notepad")
+            } else {
+                String::from("vi")
+            }
+        });
+    cmd.split_whitespace().map(String::from).collect()
+}
+
+fn temp_path() -> PathBuf {
+    let mut p = env::temp_dir();
+    p.push(format!(
+        "todo-{}-{:08x}.txt",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    p
+}
+
+/// Renders the note in the format the user edits it in.
+pub fn template(n: &Note) -> String {
+    format!(
+        "{help}{title}\ntags: {tags}\nlvl: {lvl}\ndue: {due}\n\n{body}\n",
+        help = HELP,
+        title = n.title,
+        tags = n.tags.as_deref().unwrap_or_default().join(", "),
+        lvl = n.lvl.map(|l| l.to_string()).unwrap_or_default(),
+        due = n.due.as_ref().map(note::format_due).unwrap_or_default(),
+        body = n.body,
+    )
+}
+
+/// Returns whether `l` is a line of the help written at the top of the template.
+fn is_help(l: &str) -> bool {
+    HELP.lines().any(|h| h == l.trim_end())
+}
+
+/// Parses a note edited by the user.
+/// The fields that aren't part of the template are taken from `original`.
+///
+/// Only the lines of the help are skipped before the title, which can start with '#' like any other text.
+/// Returns `None` if the text contains nothing but the help and whitespace,
+/// or if both the title and the body are empty.
+pub fn parse(s: &str, original: &Note) -> Result<Option<Note>, Box<dyn Error>> {
+    if s.lines().all(|l| l.trim().is_empty() || is_help(l)) {
+        return Ok(None);
+    }
+    let mut lines = s.lines().skip_while(|l| is_help(l));
+    let title = lines.next().unwrap_or_default().trim();
+
+    let mut tags = None;
+    let mut lvl = None;
+    let mut due = None;
+    for l in lines.by_ref() {
+        if l.trim().is_empty() {
+            break;
+        } else if l.starts_with('#') {
+            continue;
+        }
+
+        let (key, val) = match l.find(':') {
+            Some(i) => (l[..i].trim(), l[i + 1..].trim()),
+            None => return Err(format!("invalid header line: {}", l).into()),
+        };
+        match &key.to_lowercase()[..] {
+            "tags" | "tag" => {
+                let t: Vec<_> = val
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect();
+                tags = if t.is_empty() { None } else { Some(t) };
+            }
+            "lvl" | "level" if val.is_empty() => lvl = None,
+            "lvl" | "level" => {
+                lvl = Some(val.parse::<u8>().map_err(|_| {
+                    format!(
+                        "invalid importance level '{}': must be a number between 0 and 255",
+                        val
+                    )
+                })?);
+            }
+            "due" if val.is_empty() => due = None,
+            "due" => {
+                due = Some(
+                    date::parse_due(val, note::now())
+                        .ok_or_else(|| format!("invalid due date: {}", val))?,
+                );
+            }
+            _ => return Err(format!("unknown header: {}", key).into()),
+        };
+    }
+
+    let body = lines.collect::<Vec<_>>().join("\n");
+    if title.is_empty() && body.trim().is_empty() {
+        return Ok(None);
+    } else if title.is_empty() {
+        return Err("the title of the note is empty".into());
+    }
+
+    Ok(Some(Note {
+        title: title.to_owned(),
+        body: body.trim().to_owned(),
+        tags,
+        lvl,
+        due,
+        ..original.clone()
+    }))
+}
+
+/// Opens the note in the users editor and returns the result.
+///
+/// The editor is taken from `$VISUAL` or `$EDITOR`.
+/// Returns `None` if the user left the note empty.
+pub fn compose(n: &Note) -> Result<Option<Note>, Box<dyn Error>> {
+    let p = temp_path();
+    fs::write(&p, template(n))?;
+
+    let cmd = editor();
+    let status = Command::new(&cmd[0]).args(&cmd[1..]).arg(&p).status();
+    let data = fs::read_to_string(&p);
+    let _ = fs::remove_file(&p);
+
+    let status = status.map_err(|e| format!("failed to run the editor {}: {}", cmd[0], e))?;
+    if !status.success() {
+        return Err(format!("the editor exited with {}", status).into());
+    }
+    parse(&data?, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_starting_with_a_hash_survives_the_template() {
+        let n = Note {
+            tags: Some(vec!["ci".to_owned()]),
+            ..Note::new("#42 fix build", "details")
+        };
+        let parsed = parse(&template(&n), &n).unwrap().unwrap();
+        assert_eq!(parsed.title, "#42 fix build");
+        assert_eq!(parsed.tags, n.tags);
+        assert_eq!(parsed.body, "details");
+    }
+
+    #[test]
+    fn the_help_alone_is_empty() {
+        let n = Note::new("", "");
+        assert!(parse(HELP, &n).unwrap().is_none());
+        assert!(parse("# not the help\n", &n).unwrap().is_some());
+    }
+}
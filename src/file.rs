@@ -0,0 +1,236 @@
+use fs2::FileExt;
+use serde::Serialize;
+
+use std::{
+    error::Error,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub fn with_suffix(p: &Path, suffix: &str) -> PathBuf {
+    let mut name = p.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    p.with_file_name(name)
+}
+
+/// Returns the path of the backup file of `p`.
+pub fn backup_path(p: &Path) -> PathBuf {
+    with_suffix(p, ".bak")
+}
+
+fn temp_path(p: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(p.file_name().unwrap_or_default());
+    name.push(format!(".tmp-{}", std::process::id()));
+    p.with_file_name(name)
+}
+
+fn sync_dir(p: &Path) -> io::Result<()> {
+    if cfg!(unix) {
+        let dir = match p.parent() {
+            Some(d) if !d.as_os_str().is_empty() => d,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()
+    } else {
+        Ok(())
+    }
+}
+
+/// Replaces the contents of the file at `p` with `data` without ever leaving it partially written.
+///
+/// The data is written to a temporary file in the same directory,
+/// which is then synced to disk and renamed over `p`.
+/// If anything fails, the original file is left untouched.
+pub fn write_atomic(p: impl AsRef<Path>, data: &[u8]) -> io::Result<()> {
+    let p = p.as_ref();
+    let tmp = temp_path(p);
+    let res = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp)
+        .and_then(|mut f| {
+            f.write_all(data)?;
+            f.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, p));
+
+    if let Err(e) = res {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    // the rename is durable only after the directory is synced, but the data is safe either way
+    let _ = sync_dir(p);
+    Ok(())
+}
+
+/// Serializes `value` as TOML and writes it to `p` with [`write_atomic`].
+/// The value is serialized before anything is written, so a value that
+/// can't be serialized leaves the file untouched.
+pub fn save_toml(p: impl AsRef<Path>, value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    let data = toml::to_string_pretty(value)?;
+    write_atomic(p, data.as_bytes())?;
+    Ok(())
+}
+
+/// Copies the current contents of `p` to its backup file, replacing the previous backup.
+/// Does nothing if `p` doesn't exist.
+pub fn backup(p: impl AsRef<Path>) -> io::Result<()> {
+    let p = p.as_ref();
+    match fs::read(p) {
+        Ok(data) => write_atomic(backup_path(p), &data),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the path of the lock file of `p`.
+pub fn lock_path(p: &Path) -> PathBuf {
+    with_suffix(p, ".lock")
+}
+
+/// An advisory lock on a file, released when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    file: File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// Acquires an exclusive lock for `p`, waiting at most `timeout` for other processes to release it.
+///
+/// The lock is taken on a separate lock file next to `p`,
+/// because `p` itself is replaced on every write.
+pub fn lock(p: impl AsRef<Path>, timeout: Duration) -> io::Result<Lock> {
+    let path = lock_path(p.as_ref());
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+
+    let start = Instant::now();
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(_) => return Ok(Lock { file }),
+            Err(e) if e.kind() != fs2::lock_contended_error().kind() => return Err(e),
+            Err(_) if start.elapsed() >= timeout => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "could not lock {} within {} seconds, another todo process is holding it",
+                        path.display(),
+                        timeout.as_secs_f32()
+                    ),
+                ));
+            }
+            Err(_) => thread::sleep(Duration::from_millis(20)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a new empty directory for the test `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-file-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn leftovers(dir: &Path) -> Vec<OsString> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().contains(".tmp-"))
+            .collect()
+    }
+
+    struct Unserializable;
+
+    impl Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("can't serialize"))
+        }
+    }
+
+    #[test]
+    fn failed_serialization_leaves_the_original_untouched() {
+        let dir = test_dir("serialize");
+        let p = dir.join("todos.toml");
+        fs::write(&p, "old").unwrap();
+        let err = save_toml(&p, &Unserializable).unwrap_err();
+        assert!(err.to_string().contains("can't serialize"), "{}", err);
+        assert_eq!(fs::read(&p).unwrap(), b"old");
+        assert!(leftovers(&dir).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file() {
+        let dir = test_dir("replace");
+        let p = dir.join("todos.toml");
+        fs::write(&p, "old").unwrap();
+        write_atomic(&p, b"new").unwrap();
+        assert_eq!(fs::read(&p).unwrap(), b"new");
+        assert!(leftovers(&dir).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_write_atomic_leaves_the_original_untouched() {
+        let dir = test_dir("fail");
+        // renaming a file over a non-empty directory fails
+        let p = dir.join("todos.toml");
+        fs::create_dir(&p).unwrap();
+        fs::write(p.join("inside"), "original").unwrap();
+
+        assert!(write_atomic(&p, b"new").is_err());
+        assert_eq!(fs::read(p.join("inside")).unwrap(), b"original");
+        assert!(leftovers(&dir).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn backup_copies_the_previous_contents() {
+        let dir = test_dir("backup");
+        let p = dir.join("todos.toml");
+        backup(&p).unwrap();
+        assert!(!backup_path(&p).exists());
+
+        fs::write(&p, "first").unwrap();
+        backup(&p).unwrap();
+        write_atomic(&p, b"second").unwrap();
+        assert_eq!(fs::read(backup_path(&p)).unwrap(), b"first");
+        assert_eq!(fs::read(&p).unwrap(), b"second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn lock_times_out_while_another_holds_it() {
+        let dir = test_dir("lock");
+        let p = dir.join("todos.toml");
+        let held = lock(&p, Duration::ZERO).unwrap();
+
+        let start = Instant::now();
+        let err = lock(&p, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        drop(held);
+        assert!(lock(&p, Duration::ZERO).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
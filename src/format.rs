@@ -0,0 +1,248 @@
+//! Machine readable output.
+//!
+//! Every format carries the schema version, [SCHEMA_VERSION], which is increased whenever
+//! a field is removed or changes meaning; new fields can be added without increasing it.
+
+use crate::{note::Note, render::Renderer, template::Template};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Serialize;
+use serde_derive::Serialize;
+
+use std::{error::Error, str::FromStr};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    Human,
+    Json,
+    Jsonl,
+    Toml,
+    Csv,
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            "toml" => Ok(Self::Toml),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+/// A note as it appears in the machine readable output.
+#[derive(Debug, Serialize)]
+pub struct Record<'a> {
+    pub index: usize,
+    pub id: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    pub tags: &'a [String],
+    pub lvl: Option<u8>,
+    pub due: Option<DateTime<Local>>,
+    pub done: Option<DateTime<Local>>,
+    pub created: Option<DateTime<Local>>,
+    pub updated: Option<DateTime<Local>>,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(index: usize, n: &'a Note) -> Self {
+        Self {
+            index,
+            id: &n.id,
+            title: &n.title,
+            body: &n.body,
+            tags: n.tags.as_deref().unwrap_or_default(),
+            lvl: n.lvl,
+            due: n.due,
+            done: n.done,
+            created: n.created,
+            updated: n.updated,
+        }
+    }
+
+    const COLUMNS: &'static [&'static str] = &[
+        "index", "id", "title", "body", "tags", "lvl", "due", "done", "created", "updated",
+    ];
+
+    fn columns(&self) -> Vec<String> {
+        let time = |t: Option<DateTime<Local>>| {
+            t.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default()
+        };
+        vec![
+            self.index.to_string(),
+            self.id.to_owned(),
+            self.title.to_owned(),
+            self.body.to_owned(),
+            self.tags.join(","),
+            self.lvl.map(|l| l.to_string()).unwrap_or_default(),
+            time(self.due),
+            time(self.done),
+            time(self.created),
+            time(self.updated),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct Document<T: Serialize> {
+    version: u32,
+    #[serde(flatten)]
+    data: T,
+}
+
+#[derive(Serialize)]
+struct NoteList<'a> {
+    notes: &'a [Record<'a>],
+}
+
+#[derive(Serialize)]
+struct Line<'a, T: Serialize> {
+    version: u32,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+/// Renders `data`, which has to serialize to an object, as a single line of JSON with the schema version.
+pub fn json_line<T: Serialize>(data: &T) -> serde_json::Result<String> {
+    serde_json::to_string(&Line {
+        version: SCHEMA_VERSION,
+        data,
+    })
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn tsv_field(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn table<S: AsRef<str>>(f: Format, header: &[S], rows: &[Vec<String>]) -> String {
+    let (sep, escape): (&str, fn(&str) -> String) = match f {
+        Format::Csv => (",", csv_field),
+        _ => ("\t", tsv_field),
+    };
+    let mut out = String::new();
+    let line = |cols: Vec<String>| cols.join(sep) + "\n";
+    out.push_str(&line(header.iter().map(|s| escape(s.as_ref())).collect()));
+    for row in rows {
+        out.push_str(&line(row.iter().map(|s| escape(s)).collect()));
+    }
+    out
+}
+
+/// Renders the records in the given format; `f` must not be [Format::Human].
+pub fn render_notes(f: Format, records: &[Record]) -> Result<String, Box<dyn Error>> {
+    Ok(match f {
+        Format::Human => panic!("internal error: render_notes called with the human format"),
+        Format::Json => {
+            let doc = Document {
+                version: SCHEMA_VERSION,
+                data: NoteList { notes: records },
+            };
+            serde_json::to_string_pretty(&doc)? + "\n"
+        }
+        Format::Jsonl => {
+            let mut out = String::new();
+            for r in records {
+                out.push_str(&serde_json::to_string(&Line {
+                    version: SCHEMA_VERSION,
+                    data: r,
+                })?);
+                out.push('\n');
+            }
+            out
+        }
+        Format::Toml => toml::to_string_pretty(&Document {
+            version: SCHEMA_VERSION,
+            data: NoteList { notes: records },
+        })?,
+        Format::Csv | Format::Tsv => table(
+            f,
+            Record::COLUMNS,
+            &records.iter().map(Record::columns).collect::<Vec<_>>(),
+        ),
+    })
+}
+
+/// Renders a list of named values, such as file paths, in the given format; `f` must not be [Format::Human].
+pub fn render_values(f: Format, values: &[(&str, String)]) -> Result<String, Box<dyn Error>> {
+    let map: serde_json::Map<String, serde_json::Value> = values
+        .iter()
+        .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.clone())))
+        .collect();
+
+    Ok(match f {
+        Format::Human => panic!("internal error: render_values called with the human format"),
+        Format::Json => {
+            serde_json::to_string_pretty(&Document {
+                version: SCHEMA_VERSION,
+                data: map,
+            })? + "\n"
+        }
+        Format::Jsonl => {
+            serde_json::to_string(&Document {
+                version: SCHEMA_VERSION,
+                data: map,
+            })? + "\n"
+        }
+        Format::Toml => toml::to_string_pretty(&Document {
+            version: SCHEMA_VERSION,
+            data: map,
+        })?,
+        Format::Csv | Format::Tsv => table(
+            f,
+            &values.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            &[values.iter().map(|(_, v)| v.clone()).collect()],
+        ),
+    })
+}
+
+/// Prints the notes in the given format, in the same order [crate::note::print_notes_enumerated] displays them.
+/// The human format uses `template` if there is one, and `renderer` otherwise.
+pub fn print_notes(
+    f: Format,
+    template: Option<&Template>,
+    renderer: &Renderer,
+    notes: &[&(usize, Note)],
+    show_time: bool,
+) -> Result<(), Box<dyn Error>> {
+    match (f, template) {
+        (Format::Human, Some(t)) => {
+            t.print_notes(notes);
+            return Ok(());
+        }
+        (Format::Human, None) => {
+            renderer.print_notes(notes, show_time);
+            return Ok(());
+        }
+        _ => (),
+    }
+    let records: Vec<_> = notes
+        .iter()
+        .rev()
+        .map(|(i, n)| Record::new(*i, n))
+        .collect();
+    print!("{}", render_notes(f, &records)?);
+    Ok(())
+}
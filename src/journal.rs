@@ -0,0 +1,208 @@
+use crate::{file, note::Note};
+
+use chrono::{DateTime, Local};
+use serde_derive::{Deserialize, Serialize};
+
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How many operations the journal remembers.
+const MAX_ENTRIES: usize = 100;
+
+/// Returns the path of the journal of the todos file `p`.
+pub fn journal_path(p: &Path) -> PathBuf {
+    file::with_suffix(p, ".journal")
+}
+
+/// Describes an operation in a single line, such as "new: buy milk" or "remove: 3 notes".
+pub fn summary<'a>(command: &str, notes: impl IntoIterator<Item = &'a Note>) -> String {
+    let notes: Vec<_> = notes.into_iter().collect();
+    match &notes[..] {
+        [n] => format!("{}: {}", command, n.title),
+        _ => format!("{}: {} notes", command, notes.len()),
+    }
+}
+
+/// A note and its position in the todos file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub index: usize,
+    pub note: Note,
+}
+
+impl Snapshot {
+    pub fn new(index: usize, note: &Note) -> Self {
+        Self {
+            index,
+            note: note.clone(),
+        }
+    }
+}
+
+/// A single operation that modified the todos file.
+///
+/// `before` holds the affected notes as they were before the operation and `after` as they were after it;
+/// a note that was created is only in `after` and a note that was removed is only in `before`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: DateTime<Local>,
+    pub command: String,
+    pub summary: String,
+    #[serde(default)]
+    pub undone: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<Snapshot>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<Snapshot>,
+    /// The part of the operation that changed another list, such as the one notes were moved to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linked: Option<Linked>,
+}
+
+/// The notes an operation changed in another list, which is undone and redone along with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Linked {
+    /// The name of the other list.
+    pub list: String,
+    /// Its todos file, to make sure the list wasn't moved since.
+    pub file: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<Snapshot>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<Snapshot>,
+}
+
+impl Linked {
+    /// Reverts the operation on `notes`, the notes of the other list.
+    pub fn undo(&self, notes: &mut Vec<Note>) -> Result<(), String> {
+        replace(notes, &self.after, &self.before)
+    }
+
+    /// Applies the operation to `notes`, the notes of the other list, again.
+    pub fn redo(&self, notes: &mut Vec<Note>) -> Result<(), String> {
+        replace(notes, &self.before, &self.after)
+    }
+}
+
+impl Entry {
+    pub fn new(
+        command: impl AsRef<str>,
+        summary: impl AsRef<str>,
+        before: Vec<Snapshot>,
+        after: Vec<Snapshot>,
+    ) -> Self {
+        Self {
+            time: crate::note::now(),
+            command: command.as_ref().to_owned(),
+            summary: summary.as_ref().to_owned(),
+            undone: false,
+            before,
+            after,
+            linked: None,
+        }
+    }
+
+    /// Records the part of the operation that changed another list.
+    pub fn with_linked(mut self, linked: Linked) -> Self {
+        self.linked = Some(linked);
+        self
+    }
+
+    /// Reverts the operation on `notes`.
+    pub fn undo(&self, notes: &mut Vec<Note>) -> Result<(), String> {
+        replace(notes, &self.after, &self.before)
+    }
+
+    /// Applies the operation to `notes` again.
+    pub fn redo(&self, notes: &mut Vec<Note>) -> Result<(), String> {
+        replace(notes, &self.before, &self.after)
+    }
+}
+
+/// Removes the notes in `old` and inserts the ones in `new` at their positions.
+/// Fails without changing anything if a note in `old` doesn't exist anymore.
+fn replace(notes: &mut Vec<Note>, old: &[Snapshot], new: &[Snapshot]) -> Result<(), String> {
+    for s in old {
+        if !notes.iter().any(|n| n.id == s.note.id) {
+            return Err(format!(
+                "the note {} ({}) doesn't exist anymore",
+                s.note.id, s.note.title
+            ));
+        }
+    }
+    for s in new {
+        if !old.iter().any(|o| o.note.id == s.note.id) && notes.iter().any(|n| n.id == s.note.id) {
+            return Err(format!(
+                "a note with the id {} ({}) already exists",
+                s.note.id, s.note.title
+            ));
+        }
+    }
+
+    notes.retain(|n| !old.iter().any(|s| s.note.id == n.id));
+    let mut new: Vec<_> = new.iter().collect();
+    new.sort_by_key(|s| s.index);
+    for s in new {
+        let i = s.index.min(notes.len());
+        notes.insert(i, s.note.clone());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    #[serde(default)]
+    pub entry: Vec<Entry>,
+}
+
+impl Journal {
+    /// Loads the journal at `p`, an empty journal is returned if the file doesn't exist.
+    pub fn load(p: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(p.as_ref()) {
+            Ok(data) => Ok(toml::from_str(&data)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, p: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        file::save_toml(p, self)
+    }
+
+    /// Records a new operation; the operations that were undone can't be redone afterwards.
+    pub fn push(&mut self, e: Entry) {
+        self.entry.retain(|e| !e.undone);
+        self.entry.push(e);
+        if self.entry.len() > MAX_ENTRIES {
+            let n = self.entry.len() - MAX_ENTRIES;
+            self.entry.drain(..n);
+        }
+    }
+
+    /// Returns the most recent operation that can be undone.
+    pub fn last_done(&mut self) -> Option<&mut Entry> {
+        self.entry.iter_mut().rev().find(|e| !e.undone)
+    }
+
+    /// Returns the oldest operation that can be redone.
+    pub fn first_undone(&mut self) -> Option<&mut Entry> {
+        self.entry.iter_mut().find(|e| e.undone)
+    }
+
+    /// Returns the operations undoing, or redoing if `redo` is true, `n` times would apply, in that order.
+    pub fn pending(&self, n: usize, redo: bool) -> Vec<&Entry> {
+        if redo {
+            self.entry.iter().filter(|e| e.undone).take(n).collect()
+        } else {
+            self.entry
+                .iter()
+                .rev()
+                .filter(|e| !e.undone)
+                .take(n)
+                .collect()
+        }
+    }
+}
@@ -2,4 +2,12 @@
 pub mod command;
 #[path = "config/config.rs"]
 pub mod config;
+pub mod date;
+pub mod editor;
+pub mod file;
+pub mod format;
+pub mod journal;
 pub mod note;
+pub mod render;
+pub mod search;
+pub mod template;
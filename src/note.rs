@@ -1,43 +1,153 @@
+use crate::{date, file, render::Renderer};
+
+use chrono::{DateTime, Local, SubsecRound};
 use serde_derive::{Deserialize, Serialize};
 
-use std::{
-    error::Error,
-    fs::{self, File},
-    io::Write,
-    path::Path,
-};
+use std::{error::Error, fs, path::Path};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Note {
+    #[serde(default)]
+    pub id: String,
     pub title: String,
     pub body: String,
     pub tags: Option<Vec<String>>,
     pub lvl: Option<u8>,
+    pub due: Option<DateTime<Local>>,
+    pub done: Option<DateTime<Local>>,
+    pub created: Option<DateTime<Local>>,
+    pub updated: Option<DateTime<Local>>,
 }
 
 impl Note {
     pub fn new(title: impl AsRef<str>, body: impl AsRef<str>) -> Self {
+        let now = now();
         Self {
+            id: random_id(),
             title: title.as_ref().to_owned(),
             body: body.as_ref().to_owned(),
             tags: None,
             lvl: None,
+            due: None,
+            done: None,
+            created: Some(now),
+            updated: Some(now),
+        }
+    }
+
+    /// Sets the modification time of the note to now.
+    pub fn touch(&mut self) {
+        self.updated = Some(now());
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.is_some()
+    }
+
+    /// Returns true if the note isn't done and its due date has passed.
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        !self.is_done() && self.due.is_some_and(|t| t < now)
+    }
+}
+
+/// Returns the current time, truncated to seconds.
+pub fn now() -> DateTime<Local> {
+    Local::now().trunc_subsecs(0)
+}
+
+const ID_LEN: usize = 8;
+
+fn random_id() -> String {
+    let n: u32 = rand::random();
+    format!("{:0width$x}", n, width = ID_LEN)
+}
+
+/// Returns a random id that is not used by any of the given notes.
+pub fn unique_id(notes: &[Note]) -> String {
+    loop {
+        let id = random_id();
+        if !notes.iter().any(|n| n.id == id) {
+            return id;
         }
     }
 }
 
+/// Returns the index of the only note whose id starts with `prefix`.
+pub fn find_by_id(notes: &[Note], prefix: &str) -> Result<usize, String> {
+    let prefix = prefix.to_lowercase();
+    let mut found = notes
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| n.id.starts_with(&prefix[..]));
+
+    match (found.next(), found.next()) {
+        (Some((i, _)), None) => Ok(i),
+        (None, _) => Err(format!("no note has an id starting with {}", prefix)),
+        (Some(_), Some(_)) => Err(format!("the id prefix {} is ambiguous", prefix)),
+    }
+}
+
 pub fn save_notes(p: impl AsRef<Path>, notes: &Notes) -> Result<(), Box<dyn Error>> {
-    let data = toml::to_string_pretty(notes)?;
-    let mut f = File::create(p.as_ref())?;
-    f.write_all(data.as_bytes())?;
-    f.sync_all()?;
-    Ok(())
+    notes.save_to(p)
 }
 
 pub fn get_notes(p: impl AsRef<Path>) -> Result<Vec<Note>, Box<dyn Error>> {
     let data = fs::read_to_string(p.as_ref())?;
     let notes: Notes = toml::from_str(&data)?;
-    Ok(notes.todo.unwrap_or_default())
+    let mut notes = notes.todo.unwrap_or_default();
+    migrate(&mut notes, p.as_ref())?;
+    Ok(notes)
+}
+
+/// Fills in the fields missing from notes written by older versions.
+///
+/// This is done in memory only, so reading the notes never writes the file;
+/// the fields are saved along with the next change.
+fn migrate(notes: &mut [Note], p: &Path) -> Result<(), Box<dyn Error>> {
+    for i in 0..notes.len() {
+        if notes[i].id.is_empty() {
+            notes[i].id = derived_id(notes, i);
+        }
+    }
+
+    // there's no way to know when old notes were created,
+    // the last time the file was modified is the closest guess
+    if notes
+        .iter()
+        .any(|n| n.created.is_none() || n.updated.is_none())
+    {
+        let modified: DateTime<Local> = fs::metadata(p)?.modified()?.into();
+        let modified = modified.trunc_subsecs(0);
+        for n in notes.iter_mut() {
+            let created = *n.created.get_or_insert(modified);
+            n.updated.get_or_insert(created);
+        }
+    }
+    Ok(())
+}
+
+/// Returns an id for the note at `i` that's derived from its position and contents,
+/// so reading the same file always gives it the same id until it's saved.
+fn derived_id(notes: &[Note], i: usize) -> String {
+    // 32 bit FNV-1a, whose result doesn't change between versions like the std hasher's could
+    let hash = |seed: u32| {
+        let data = [
+            &(i as u64).to_le_bytes()[..],
+            &seed.to_le_bytes(),
+            notes[i].title.as_bytes(),
+            &[0],
+            notes[i].body.as_bytes(),
+        ];
+        data.iter()
+            .flat_map(|d| d.iter())
+            .fold(0x811c_9dc5_u32, |h, &b| {
+                (h ^ b as u32).wrapping_mul(0x0100_0193)
+            })
+    };
+    (0..)
+        .map(|seed| format!("{:0width$x}", hash(seed), width = ID_LEN))
+        .find(|id| !notes.iter().any(|n| &n.id == id))
+        .expect("internal error: ran out of ids")
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -50,45 +160,84 @@ impl Notes {
         Self { todo: Some(v) }
     }
 
+    /// Saves the notes to `p` atomically; if saving fails, the file is left untouched.
     pub fn save_to(&self, p: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
-        let data = toml::to_string_pretty(self)?;
-        let mut f = File::create(p.as_ref())?;
-        f.write_all(data.as_bytes())?;
-        f.sync_all()?;
-        Ok(())
+        file::save_toml(p, self)
+    }
+}
+
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Formats a due date, omitting the time if it's the end of the day.
+pub fn format_due(t: &DateTime<Local>) -> String {
+    if date::is_end_of_day(t) {
+        t.format("%Y-%m-%d").to_string()
+    } else {
+        t.format(TIME_FORMAT).to_string()
+    }
+}
+
+pub(crate) fn status(n: &Note, show_time: bool) -> String {
+    let mut fields = Vec::new();
+    if let Some(t) = &n.due {
+        if n.is_overdue(now()) {
+            fields.push(format!("OVERDUE, due {}", format_due(t)));
+        } else {
+            fields.push(format!("due {}", format_due(t)));
+        }
+    }
+    if let Some(t) = n.done {
+        fields.push(format!("done {}", t.format("%Y-%m-%d")));
+    }
+    if show_time {
+        if let Some(t) = n.created {
+            fields.push(format!("created {}", t.format(TIME_FORMAT)));
+        }
+        if let Some(t) = n.updated {
+            fields.push(format!("updated {}", t.format(TIME_FORMAT)));
+        }
+    }
+
+    if fields.is_empty() {
+        String::new()
+    } else {
+        format!("  ({})", fields.join(", "))
     }
 }
 
+/// Prints the notes without colors, the last one first.
 pub fn print_notes(notes: &[Note]) {
-    if notes.is_empty() {
-        println!("no results");
-        return;
-    }
-    let max_title = notes.iter().map(|n| n.title.len()).max().unwrap_or(4);
-    for (i, n) in notes.iter().enumerate().rev() {
-        println!(
-            "#{index:2}  {title:width$}  |  {body}",
-            index = i,
-            width = max_title,
-            title = n.title,
-            body = n.body
-        );
-    }
-}
-
-pub fn print_notes_enumerated(notes: &[&(usize, Note)]) {
-    if notes.is_empty() {
-        println!("no results");
-        return;
-    }
-    let max_title = notes.iter().map(|(_, n)| n.title.len()).max().unwrap_or(4);
-    for (i, n) in notes.iter().rev() {
-        println!(
-            "#{index:2}  {title:width$}  |  {body}",
-            index = i,
-            width = max_title,
-            title = n.title,
-            body = n.body
-        );
+    let notes: Vec<_> = notes.iter().cloned().enumerate().collect();
+    print_notes_enumerated(&notes.iter().collect::<Vec<_>>(), false);
+}
+
+/// Prints the notes without colors, the last one first. See [Renderer] for colored output.
+pub fn print_notes_enumerated(notes: &[&(usize, Note)], show_time: bool) {
+    Renderer::default().print_notes(notes, show_time);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_notes_are_migrated_without_writing_the_file() {
+        let dir = std::env::temp_dir().join(format!("todo-note-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("todos.toml");
+        let data = "[[todo]]\ntitle = \"a\"\nbody = \"\"\n\n[[todo]]\ntitle = \"a\"\nbody = \"\"\n";
+        fs::write(&p, data).unwrap();
+
+        let first = get_notes(&p).unwrap();
+        let second = get_notes(&p).unwrap();
+        assert_eq!(fs::read_to_string(&p).unwrap(), data);
+        assert!(first
+            .iter()
+            .all(|n| n.id.len() == ID_LEN && n.created.is_some()));
+        assert_ne!(first[0].id, first[1].id);
+        let ids = |notes: &[Note]| notes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&first), ids(&second));
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
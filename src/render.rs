@@ -0,0 +1,492 @@
+//! Displays notes in the terminal.
+
+use crate::{
+    config::theme::Theme,
+    note::{self, Note},
+    search::Matcher,
+};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use std::{
+    cmp::Reverse,
+    env,
+    io::{self, IsTerminal},
+    ops::Range,
+    str::FromStr,
+};
+
+/// Bodies aren't wrapped into less columns than this, the titles are truncated instead.
+const MIN_BODY_WIDTH: usize = 20;
+/// Titles aren't truncated to less columns than this.
+const MIN_TITLE_WIDTH: usize = 10;
+/// Bodies narrower than this aren't wrapped at all.
+const MIN_WRAP_WIDTH: usize = 10;
+
+/// Returns the display width of `s` in a terminal.
+pub fn width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Truncates `s` to at most `max` columns, ending it with `…` if anything was cut.
+pub fn truncate(s: &str, max: usize) -> String {
+    if width(s) <= max {
+        return s.to_owned();
+    }
+    let mut out = String::new();
+    let mut w = 0;
+    for c in s.chars() {
+        let cw = c.width().unwrap_or(0);
+        if w + cw + 1 > max {
+            break;
+        }
+        out.push(c);
+        w += cw;
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// Pads `s` with spaces to `w` columns, on the left if `right` is true.
+pub fn pad(s: &str, w: usize, right: bool) -> String {
+    let fill = " ".repeat(w.saturating_sub(width(s)));
+    if right {
+        fill + s
+    } else {
+        format!("{}{}", s, fill)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("unknown color choice: {}", s)),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Returns true if the output should be colored;
+    /// in auto mode, only if the standard output is a terminal and `NO_COLOR` isn't set.
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|s| s.is_empty()) && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// A set of ANSI text attributes.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Style {
+    codes: Vec<u8>,
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const COLORS: &[&str] = &[
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+        let color = |name: &str| COLORS.iter().position(|c| *c == name).map(|i| i as u8);
+
+        let mut codes = Vec::new();
+        for word in s.split_whitespace() {
+            let word = word.to_lowercase();
+            let code = match &word[..] {
+                "bold" => Some(1),
+                "dimmed" | "dim" => Some(2),
+                "italic" => Some(3),
+                "underline" => Some(4),
+                "reverse" => Some(7),
+                w => {
+                    if let Some(c) = w.strip_prefix("on_bright_") {
+                        color(c).map(|c| 100 + c)
+                    } else if let Some(c) = w.strip_prefix("on_") {
+                        color(c).map(|c| 40 + c)
+                    } else if let Some(c) = w.strip_prefix("bright_") {
+                        color(c).map(|c| 90 + c)
+                    } else {
+                        color(w).map(|c| 30 + c)
+                    }
+                }
+            };
+            codes.push(code.ok_or_else(|| format!("unknown color or attribute: {}", word))?);
+        }
+        Ok(Self { codes })
+    }
+}
+
+impl Style {
+    /// Returns a style with the attributes of both.
+    fn join(&self, other: &Style) -> Style {
+        Style {
+            codes: self.codes.iter().chain(&other.codes).copied().collect(),
+        }
+    }
+
+    fn paint(&self, s: &str, color: bool) -> String {
+        if !color || self.codes.is_empty() || s.is_empty() {
+            return s.to_owned();
+        }
+        let codes: Vec<_> = self.codes.iter().map(u8::to_string).collect();
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), s)
+    }
+}
+
+/// The theme with its colors parsed.
+#[derive(Debug, Default)]
+struct Styles {
+    index: Style,
+    id: Style,
+    title: Style,
+    body: Style,
+    status: Style,
+    overdue: Style,
+    done: Style,
+    highlight: Style,
+    /// Sorted by the level, the highest first.
+    lvl: Vec<(u8, Style)>,
+    tags: Vec<(String, Style)>,
+}
+
+impl Styles {
+    fn new(t: &Theme) -> Result<Self, String> {
+        let parse = |name: &str, s: &str| -> Result<Style, String> {
+            s.parse()
+                .map_err(|e| format!("invalid color in theme.{}: {}", name, e))
+        };
+
+        let mut lvl = Vec::with_capacity(t.lvl.len());
+        for (k, v) in &t.lvl {
+            let n = k.parse::<u8>().map_err(|_| {
+                format!(
+                    "invalid level in theme.lvl: {}, must be a number between 0 and 255",
+                    k
+                )
+            })?;
+            lvl.push((n, parse("lvl", v)?));
+        }
+        lvl.sort_by_key(|(l, _)| Reverse(*l));
+
+        let mut tags = Vec::with_capacity(t.tags.len());
+        for (k, v) in &t.tags {
+            tags.push((k.to_lowercase(), parse("tags", v)?));
+        }
+
+        Ok(Self {
+            index: parse("index", &t.index)?,
+            id: parse("id", &t.id)?,
+            title: parse("title", &t.title)?,
+            body: parse("body", &t.body)?,
+            status: parse("status", &t.status)?,
+            overdue: parse("overdue", &t.overdue)?,
+            done: parse("done", &t.done)?,
+            highlight: parse("highlight", &t.highlight)?,
+            lvl,
+            tags,
+        })
+    }
+
+    fn title(&self, n: &Note) -> &Style {
+        let by_lvl = n
+            .lvl
+            .and_then(|l| self.lvl.iter().find(|(min, _)| l >= *min))
+            .map(|(_, s)| s);
+        let by_tag = || {
+            n.tags.as_deref().unwrap_or_default().iter().find_map(|t| {
+                let t = t.to_lowercase();
+                self.tags
+                    .iter()
+                    .find(|(name, _)| *name == t)
+                    .map(|(_, s)| s)
+            })
+        };
+        by_lvl.or_else(by_tag).unwrap_or(&self.title)
+    }
+}
+
+/// Displays notes in a terminal, fitting them to its width.
+#[derive(Debug, Default)]
+pub struct Renderer<'a> {
+    styles: Styles,
+    color: bool,
+    /// The width of the terminal, None if the output isn't a terminal.
+    columns: Option<usize>,
+    /// Highlights the matches of this search.
+    search: Option<&'a Matcher>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(theme: &Theme, color: ColorChoice) -> Result<Self, String> {
+        let columns = if io::stdout().is_terminal() {
+            terminal_size::terminal_size().map(|(w, _)| w.0 as usize)
+        } else {
+            None
+        };
+        Ok(Self {
+            styles: Styles::new(theme)?,
+            color: color.enabled(),
+            columns,
+            search: None,
+        })
+    }
+
+    /// Highlights the parts of the notes that match `m`, and shows their matching tags.
+    pub fn with_search(self, m: &'a Matcher) -> Self {
+        Self {
+            search: Some(m),
+            ..self
+        }
+    }
+
+    fn paint(&self, style: &Style, s: &str) -> String {
+        style.paint(s, self.color)
+    }
+
+    /// Paints `text`, highlighting the `marks`; `offset` is where `text` starts in the string the marks refer to.
+    fn paint_marked(
+        &self,
+        style: &Style,
+        text: &str,
+        offset: usize,
+        marks: &[Range<usize>],
+    ) -> String {
+        let highlight = style.join(&self.styles.highlight);
+        let mut out = String::new();
+        let mut pos = 0;
+        for m in marks {
+            let start = m.start.saturating_sub(offset).min(text.len());
+            let end = m.end.saturating_sub(offset).min(text.len());
+            if end <= pos {
+                continue;
+            }
+            let start = start.max(pos);
+            out.push_str(&self.paint(style, &text[pos..start]));
+            out.push_str(&self.paint(&highlight, &text[start..end]));
+            pos = end;
+        }
+        out.push_str(&self.paint(style, &text[pos..]));
+        out
+    }
+
+    /// Prints the notes, the last one first.
+    pub fn print_notes(&self, notes: &[&(usize, Note)], show_time: bool) {
+        if notes.is_empty() {
+            println!("no results");
+            return;
+        }
+
+        let index_width = notes
+            .iter()
+            .map(|(i, _)| i.to_string().len())
+            .max()
+            .unwrap_or(1)
+            .max(2);
+        let id_width = notes.iter().map(|(_, n)| width(&n.id)).max().unwrap_or(0);
+        let mut title_width = notes
+            .iter()
+            .map(|(_, n)| width(&n.title))
+            .max()
+            .unwrap_or(4);
+        // "#" index "  " id "  " title "  |  " body
+        let fixed = 1 + index_width + 2 + id_width + 2 + 5;
+        if let Some(cols) = self.columns {
+            let max = cols.saturating_sub(fixed + MIN_BODY_WIDTH);
+            title_width = title_width.min(max.max(MIN_TITLE_WIDTH));
+        }
+        let body_width = self
+            .columns
+            .map(|cols| cols.saturating_sub(fixed + title_width))
+            .filter(|&w| w >= MIN_WRAP_WIDTH);
+
+        for (i, n) in notes.iter().rev() {
+            let done = n.is_done();
+            let pick = |s| if done { &self.styles.done } else { s };
+            let hit = self.search.and_then(|m| m.search(n)).unwrap_or_default();
+
+            let title_style = pick(self.styles.title(n));
+            let title = truncate(&n.title, title_width);
+            let mut painted_title = match title.strip_suffix('…') {
+                Some(t) if title != n.title => {
+                    self.paint_marked(title_style, t, 0, &hit.title) + &self.paint(title_style, "…")
+                }
+                _ => self.paint_marked(title_style, &title, 0, &hit.title),
+            };
+            // the padding is left uncolored, it would show with a background color
+            painted_title.push_str(&" ".repeat(title_width - width(&title)));
+            let prefix = format!(
+                "#{}  {}  {}  |  ",
+                self.paint(
+                    pick(&self.styles.index),
+                    &pad(&i.to_string(), index_width, true)
+                ),
+                self.paint(pick(&self.styles.id), &pad(&n.id, id_width, false)),
+                painted_title,
+            );
+
+            let status_style = if n.is_overdue(note::now()) {
+                &self.styles.overdue
+            } else {
+                pick(&self.styles.status)
+            };
+            let status = note::status(n, show_time);
+            let body_style = pick(&self.styles.body);
+
+            // the matching tags of a search
+            let mut tags = String::new();
+            let mut tag_marks = Vec::new();
+            if !hit.tags.is_empty() {
+                tags.push('[');
+                for (k, t) in n.tags.as_deref().unwrap_or_default().iter().enumerate() {
+                    if k > 0 {
+                        tags.push_str(", ");
+                    }
+                    if hit.tags.contains(&k) {
+                        tag_marks.push(tags.len()..tags.len() + t.len());
+                    }
+                    tags.push_str(t);
+                }
+                tags.push(']');
+            }
+            let tags_style = pick(&self.styles.status);
+
+            match body_width {
+                None => {
+                    let mut line = prefix + &self.paint_marked(body_style, &n.body, 0, &hit.body);
+                    if !tags.is_empty() {
+                        line.push_str("  ");
+                        line.push_str(&self.paint_marked(tags_style, &tags, 0, &tag_marks));
+                    }
+                    line.push_str(&self.paint(status_style, &status));
+                    println!("{}", line);
+                }
+                Some(w) => {
+                    let mut words = Vec::new();
+                    push_words(&mut words, &n.body, body_style, &hit.body);
+                    push_words(&mut words, &tags, tags_style, &tag_marks);
+                    push_words(&mut words, &status, status_style, &[]);
+
+                    let indent = " ".repeat(fixed + title_width);
+                    for (k, line) in self.wrap(&words, w).iter().enumerate() {
+                        if k == 0 {
+                            println!("{}{}", prefix, line);
+                        } else {
+                            println!("{}{}", indent, line);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wraps the words into lines of at most `max` columns, breaking words longer than that.
+    fn wrap(&self, words: &[Word], max: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut w = 0;
+        for word in words {
+            let Word::Text {
+                text,
+                offset,
+                style,
+                marks,
+            } = *word
+            else {
+                lines.push(std::mem::take(&mut line));
+                w = 0;
+                continue;
+            };
+
+            // (start, end) byte offsets of the pieces of the word that fit in a line
+            let mut chunks = Vec::new();
+            let mut start = 0;
+            let mut cw = 0;
+            for (i, c) in text.char_indices() {
+                let n = c.width().unwrap_or(0);
+                if cw + n > max && i > start {
+                    chunks.push((start, i));
+                    start = i;
+                    cw = 0;
+                }
+                cw += n;
+            }
+            chunks.push((start, text.len()));
+
+            for (start, end) in chunks {
+                let chunk = &text[start..end];
+                let cw = width(chunk);
+                if w > 0 && w + 1 + cw > max {
+                    lines.push(std::mem::take(&mut line));
+                    w = 0;
+                }
+                if w > 0 {
+                    line.push(' ');
+                    w += 1;
+                }
+                line.push_str(&self.paint_marked(style, chunk, offset + start, marks));
+                w += cw;
+            }
+        }
+        lines.push(line);
+        lines
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Word<'a> {
+    Text {
+        text: &'a str,
+        /// Where the word starts in the text `marks` refer to.
+        offset: usize,
+        style: &'a Style,
+        marks: &'a [Range<usize>],
+    },
+    Break,
+}
+
+/// Splits `text` into words, keeping its line breaks.
+fn push_words<'a>(
+    words: &mut Vec<Word<'a>>,
+    text: &'a str,
+    style: &'a Style,
+    marks: &'a [Range<usize>],
+) {
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        if !c.is_whitespace() {
+            start.get_or_insert(i);
+            continue;
+        }
+        if let Some(s) = start.take() {
+            words.push(Word::Text {
+                text: &text[s..i],
+                offset: s,
+                style,
+                marks,
+            });
+        }
+        if c == '\n' {
+            words.push(Word::Break);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! Searching notes by their title, body and tags.
+
+use crate::note::Note;
+
+use regex::{Regex, RegexBuilder};
+
+use std::ops::Range;
+
+/// How much a match in each field is worth, relative to the others.
+const TITLE_WEIGHT: i64 = 3;
+const TAG_WEIGHT: i64 = 2;
+const BODY_WEIGHT: i64 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// The pattern is found anywhere in the text.
+    #[default]
+    Substring,
+    /// The pattern is a regular expression.
+    Regex,
+    /// The characters of the pattern are found in the text in order, but not necessarily next to each other.
+    Fuzzy,
+}
+
+#[derive(Debug)]
+enum Pattern {
+    Substring(Vec<char>),
+    Regex(Regex),
+    Fuzzy(Vec<char>),
+}
+
+/// A search as given on the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    pub pattern: String,
+    pub mode: Mode,
+    pub case_sensitive: bool,
+}
+
+impl Search {
+    pub fn matcher(&self) -> Result<Matcher, String> {
+        Matcher::new(&self.pattern, self.mode, self.case_sensitive)
+    }
+}
+
+/// A search pattern.
+#[derive(Debug)]
+pub struct Matcher {
+    pattern: Pattern,
+    case_sensitive: bool,
+}
+
+/// Where a note matched a search, and how well.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Hit {
+    /// Higher is better.
+    pub score: i64,
+    /// The matched byte ranges in the title.
+    pub title: Vec<Range<usize>>,
+    /// The matched byte ranges in the body.
+    pub body: Vec<Range<usize>>,
+    /// The indices of the matched tags.
+    pub tags: Vec<usize>,
+}
+
+/// A character of the searched text, with the byte range it came from.
+struct Char {
+    c: char,
+    range: Range<usize>,
+    word_start: bool,
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, mode: Mode, case_sensitive: bool) -> Result<Self, String> {
+        let fold = |s: &str| -> Vec<char> {
+            if case_sensitive {
+                s.chars().collect()
+            } else {
+                s.chars().flat_map(char::to_lowercase).collect()
+            }
+        };
+        let pattern = match mode {
+            Mode::Substring => Pattern::Substring(fold(pattern)),
+            Mode::Fuzzy => Pattern::Fuzzy(
+                fold(pattern)
+                    .into_iter()
+                    .filter(|c| !c.is_whitespace())
+                    .collect(),
+            ),
+            Mode::Regex => Pattern::Regex(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| {
+                        // the error spans several lines, pointing at the problem; the last one describes it
+                        let e = e.to_string();
+                        let reason = e
+                            .lines()
+                            .last()
+                            .unwrap_or_default()
+                            .trim_start_matches("error: ");
+                        format!("invalid regex {:?}: {}", pattern, reason)
+                    })?,
+            ),
+        };
+        Ok(Self {
+            pattern,
+            case_sensitive,
+        })
+    }
+
+    fn chars(&self, s: &str) -> Vec<Char> {
+        let mut out = Vec::with_capacity(s.len());
+        let mut prev_alnum = false;
+        for (i, c) in s.char_indices() {
+            let range = i..i + c.len_utf8();
+            let word_start = c.is_alphanumeric() && !prev_alnum;
+            prev_alnum = c.is_alphanumeric();
+            if self.case_sensitive {
+                out.push(Char {
+                    c,
+                    range,
+                    word_start,
+                });
+            } else {
+                for c in c.to_lowercase() {
+                    out.push(Char {
+                        c,
+                        range: range.clone(),
+                        word_start,
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    /// Searches `s`, returning the score and the matched byte ranges.
+    fn find(&self, s: &str) -> Option<(i64, Vec<Range<usize>>)> {
+        match &self.pattern {
+            Pattern::Substring(p) => {
+                let chars = self.chars(s);
+                let mut spans = Vec::new();
+                let mut i = 0;
+                while !p.is_empty() && i + p.len() <= chars.len() {
+                    if chars[i..i + p.len()].iter().zip(p).all(|(a, b)| a.c == *b) {
+                        let start = chars[i].range.start;
+                        let end = chars[i + p.len() - 1].range.end;
+                        spans.push(start..end);
+                        i += p.len();
+                    } else {
+                        i += 1;
+                    }
+                }
+                score_spans(s, spans)
+            }
+            Pattern::Regex(re) => score_spans(
+                s,
+                re.find_iter(s)
+                    .filter(|m| !m.range().is_empty())
+                    .map(|m| m.range())
+                    .collect(),
+            ),
+            Pattern::Fuzzy(p) => self.fuzzy(s, p),
+        }
+    }
+
+    /// Matches the characters of `p` in order, preferring consecutive characters and word starts.
+    fn fuzzy(&self, s: &str, p: &[char]) -> Option<(i64, Vec<Range<usize>>)> {
+        if p.is_empty() {
+            return None;
+        }
+        let chars = self.chars(s);
+
+        // find the first place the whole pattern matches
+        let mut end = 0;
+        let mut k = 0;
+        while k < p.len() {
+            let i = (end..chars.len()).find(|&i| chars[i].c == p[k])?;
+            end = i + 1;
+            k += 1;
+        }
+        // then walk back from its end to find the shortest window
+        let mut start = end;
+        for &c in p.iter().rev() {
+            start = (0..start).rev().find(|&i| chars[i].c == c)?;
+        }
+        // and match forward again inside it, collecting the positions
+        let mut positions = Vec::with_capacity(p.len());
+        let mut i = start;
+        for &c in p {
+            i = (i..end).find(|&j| chars[j].c == c)?;
+            positions.push(i);
+            i += 1;
+        }
+
+        let mut score = 0;
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        for (k, &i) in positions.iter().enumerate() {
+            score += 16;
+            if chars[i].word_start {
+                score += 8;
+            }
+            match k.checked_sub(1).map(|k| positions[k]) {
+                Some(prev) if prev + 1 == i => score += 8,
+                Some(prev) => score -= (i - prev - 1).min(8) as i64,
+                None => (),
+            }
+            let r = chars[i].range.clone();
+            match spans.last_mut() {
+                // a character that lowercases to several characters is matched once
+                Some(last) if r.end <= last.end => (),
+                Some(last) if last.end == r.start => last.end = r.end,
+                _ => spans.push(r),
+            }
+        }
+        Some((score.max(1), spans))
+    }
+
+    /// Searches the title, body and tags of the note; returns None if nothing matched.
+    pub fn search(&self, n: &Note) -> Option<Hit> {
+        let mut hit = Hit::default();
+        if let Some((score, spans)) = self.find(&n.title) {
+            hit.score += score * TITLE_WEIGHT;
+            hit.title = spans;
+        }
+        if let Some((score, spans)) = self.find(&n.body) {
+            hit.score += score * BODY_WEIGHT;
+            hit.body = spans;
+        }
+        for (i, t) in n.tags.as_deref().unwrap_or_default().iter().enumerate() {
+            if let Some((score, _)) = self.find(t) {
+                hit.score += score * TAG_WEIGHT;
+                hit.tags.push(i);
+            }
+        }
+
+        if hit.title.is_empty() && hit.body.is_empty() && hit.tags.is_empty() {
+            None
+        } else {
+            Some(hit)
+        }
+    }
+
+    pub fn is_match(&self, n: &Note) -> bool {
+        self.search(n).is_some()
+    }
+}
+
+/// Scores exact matches: every match counts, more so at the start of a word or if it's the whole text.
+fn score_spans(s: &str, spans: Vec<Range<usize>>) -> Option<(i64, Vec<Range<usize>>)> {
+    if spans.is_empty() {
+        return None;
+    }
+    let mut score = 0;
+    for r in &spans {
+        score += 10;
+        let word_start = s[..r.start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        if word_start {
+            score += 5;
+        }
+        if r.len() == s.len() {
+            score += 20;
+        }
+    }
+    Some((score, spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, body: &str, tags: &[&str]) -> Note {
+        let mut n = Note::new(title, body);
+        if !tags.is_empty() {
+            n.tags = Some(tags.iter().map(|s| s.to_string()).collect());
+        }
+        n
+    }
+
+    fn score(pattern: &str, mode: Mode, n: &Note) -> i64 {
+        Matcher::new(pattern, mode, false)
+            .unwrap()
+            .search(n)
+            .map_or(0, |h| h.score)
+    }
+
+    #[test]
+    fn title_beats_tag_beats_body() {
+        let title = note("buy milk", "", &[]);
+        let tag = note("errands", "", &["buy milk"]);
+        let body = note("errands", "buy milk", &[]);
+        for mode in &[Mode::Substring, Mode::Regex, Mode::Fuzzy] {
+            let (t, g, b) = (
+                score("milk", *mode, &title),
+                score("milk", *mode, &tag),
+                score("milk", *mode, &body),
+            );
+            assert!(t > g && g > b && b > 0, "{:?}: {} {} {}", mode, t, g, b);
+        }
+    }
+
+    #[test]
+    fn exact_beats_fuzzy() {
+        let score = |mode, title| score("milk", mode, &note(title, "", &[]));
+
+        // the whole title beats a word, which beats a part of a word
+        let s = Mode::Substring;
+        assert!(score(s, "milk") > score(s, "buy milk"));
+        assert!(score(s, "buy milk") > score(s, "buttermilk"));
+        assert_eq!(score(s, "make it look kind"), 0);
+
+        // consecutive characters at the start of a word beat scattered ones
+        let f = Mode::Fuzzy;
+        assert!(score(f, "buy milk") > score(f, "buttermilk"));
+        assert!(score(f, "buttermilk") > score(f, "make it look kind"));
+        assert!(score(f, "m-i-l-k") > 0);
+    }
+
+    #[test]
+    fn fuzzy_matches_in_order() {
+        let n = note("make it look kind", "", &[]);
+        let hit = Matcher::new("m ilk", Mode::Fuzzy, false)
+            .unwrap()
+            .search(&n)
+            .unwrap();
+        let matched: Vec<_> = hit.title.iter().map(|r| &n.title[r.clone()]).collect();
+        assert_eq!(matched, ["m", "i", "l", "k"]);
+        assert_eq!(score("klim", Mode::Fuzzy, &n), 0);
+    }
+
+    #[test]
+    fn regex_matches_and_case() {
+        let n = note("Buy Milk", "two bottles", &[]);
+        let hit = Matcher::new("m[a-z]+k|bottles?", Mode::Regex, false)
+            .unwrap()
+            .search(&n)
+            .unwrap();
+        let title: Vec<_> = hit.title.iter().map(|r| &n.title[r.clone()]).collect();
+        let body: Vec<_> = hit.body.iter().map(|r| &n.body[r.clone()]).collect();
+        assert_eq!((title, body), (vec!["Milk"], vec!["bottles"]));
+        assert!(Matcher::new("milk", Mode::Regex, true)
+            .unwrap()
+            .search(&n)
+            .is_none());
+        assert!(Matcher::new("MILK", Mode::Substring, false)
+            .unwrap()
+            .is_match(&n));
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        for p in &["(milk", "[a-", "*"] {
+            let e = Matcher::new(p, Mode::Regex, false).unwrap_err();
+            assert!(e.starts_with("invalid regex"), "{}", e);
+        }
+    }
+}
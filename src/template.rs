@@ -0,0 +1,395 @@
+//! Templates for printing notes.
+//!
+//! A template is plain text with these tags:
+//!
+//! - `{field}` is replaced by a field of the note, such as `{title}`.
+//! - `{field:20}` pads the field to 20 columns, `{field:>20}` aligns it to the right.
+//! - `{field:.20}` truncates the field to 20 columns; `{field:20.20}` does both.
+//! - `{?field}...{/field}` is rendered only if the field is not empty, `{!field}...{/field}` only if it is.
+//!
+//! `{{` and `}}` are literal braces; `\n`, `\t` and `\\` are a newline, a tab and a backslash.
+
+use crate::{
+    note::{self, Note, TIME_FORMAT},
+    render,
+};
+
+use chrono::{DateTime, Local};
+
+use std::{iter::Peekable, str::CharIndices, str::FromStr};
+
+/// The fields that can be used in a template.
+pub const FIELDS: &[&str] = &[
+    "index", "id", "title", "body", "tags", "lvl", "due", "done", "created", "updated", "status",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Spec {
+    align: Align,
+    width: Option<usize>,
+    max: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Field(String, Spec),
+    Cond {
+        field: String,
+        negated: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// A parsed template.
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser {
+            src: s,
+            chars: s.char_indices().peekable(),
+        };
+        let nodes = p.parse(None)?;
+        Ok(Self { nodes })
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, pos: usize, msg: impl AsRef<str>) -> String {
+        let col = self.src[..pos].chars().count() + 1;
+        format!("template error at column {}: {}", col, msg.as_ref())
+    }
+
+    /// Parses nodes until the end of the input, or until `{/name}` if `closing` is the name and position of a conditional.
+    fn parse(&mut self, closing: Option<(&str, usize)>) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+
+        while let Some((pos, c)) = self.chars.next() {
+            match c {
+                '\\' => match self.chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, '\\')) => text.push('\\'),
+                    _ => return Err(self.error(pos, "unknown escape, expected \\n, \\t or \\\\")),
+                },
+                '}' => {
+                    if let Some((_, '}')) = self.chars.peek() {
+                        self.chars.next();
+                        text.push('}');
+                    } else {
+                        return Err(self.error(pos, "unmatched }, write }} for a literal brace"));
+                    }
+                }
+                '{' if matches!(self.chars.peek(), Some((_, '{'))) => {
+                    self.chars.next();
+                    text.push('{');
+                }
+                '{' => {
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    let tag = self.tag(pos)?;
+                    if let Some(name) = tag.strip_prefix('/') {
+                        return match closing {
+                            Some((open, _)) if open == name => Ok(nodes),
+                            Some((open, _)) => Err(self.error(
+                                pos,
+                                format!("expected {{/{}}}, found {{/{}}}", open, name),
+                            )),
+                            None => Err(self.error(pos, format!("{{/{}}} closes nothing", name))),
+                        };
+                    }
+
+                    let negated = tag.starts_with('!');
+                    if let Some(name) = tag.strip_prefix(['?', '!']) {
+                        self.check_field(pos, name)?;
+                        let body = self.parse(Some((name, pos)))?;
+                        nodes.push(Node::Cond {
+                            field: name.to_owned(),
+                            negated,
+                            body,
+                        });
+                    } else {
+                        let (name, spec) = tag.split_once(':').unwrap_or((tag, ""));
+                        self.check_field(pos, name)?;
+                        let spec = parse_spec(spec).ok_or_else(|| {
+                            self.error(pos, format!("invalid format in {{{}}}", tag))
+                        })?;
+                        nodes.push(Node::Field(name.to_owned(), spec));
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+
+        if let Some((name, pos)) = closing {
+            return Err(self.error(
+                pos,
+                format!("{{?{}}} is never closed with {{/{}}}", name, name),
+            ));
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(nodes)
+    }
+
+    /// Reads the contents of a tag that starts at `start`, the opening brace is already consumed.
+    fn tag(&mut self, start: usize) -> Result<&'a str, String> {
+        for (pos, c) in self.chars.by_ref() {
+            match c {
+                '}' => return Ok(&self.src[start + 1..pos]),
+                '{' => break,
+                _ => (),
+            }
+        }
+        Err(self.error(start, "unclosed {, write {{ for a literal brace"))
+    }
+
+    fn check_field(&self, pos: usize, name: &str) -> Result<(), String> {
+        if FIELDS.contains(&name) {
+            Ok(())
+        } else {
+            Err(self.error(
+                pos,
+                format!(
+                    "unknown field {:?}, possible fields are {}",
+                    name,
+                    FIELDS.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+/// Parses a format such as `20`, `>20`, `.10` or `20.10`.
+fn parse_spec(s: &str) -> Option<Spec> {
+    let (align, s) = match s.strip_prefix('>') {
+        Some(rest) => (Align::Right, rest),
+        None => (Align::Left, s.strip_prefix('<').unwrap_or(s)),
+    };
+    let (width, max) = s.split_once('.').unwrap_or((s, ""));
+    let number = |s: &str| -> Option<Option<usize>> {
+        if s.is_empty() {
+            Some(None)
+        } else {
+            s.parse().ok().map(Some)
+        }
+    };
+    let width = number(width)?;
+    let max = if s.contains('.') {
+        Some(max.parse().ok()?)
+    } else {
+        None
+    };
+    Some(Spec { align, width, max })
+}
+
+fn time(t: Option<DateTime<Local>>) -> String {
+    t.map(|t| t.format(TIME_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+fn field(name: &str, index: usize, n: &Note) -> String {
+    match name {
+        "index" => index.to_string(),
+        "id" => n.id.clone(),
+        "title" => n.title.clone(),
+        "body" => n.body.clone(),
+        "tags" => n.tags.as_deref().unwrap_or_default().join(", "),
+        "lvl" => n.lvl.map(|l| l.to_string()).unwrap_or_default(),
+        "due" => n.due.as_ref().map(note::format_due).unwrap_or_default(),
+        "done" => time(n.done),
+        "created" => time(n.created),
+        "updated" => time(n.updated),
+        "status" => {
+            if n.is_done() {
+                "done".to_owned()
+            } else if n.is_overdue(note::now()) {
+                "overdue".to_owned()
+            } else {
+                String::new()
+            }
+        }
+        _ => panic!("internal error: unknown template field {}", name),
+    }
+}
+
+fn apply(spec: &Spec, s: String) -> String {
+    let s = match spec.max {
+        Some(max) => render::truncate(&s, max),
+        None => s,
+    };
+    match spec.width {
+        Some(w) => render::pad(&s, w, spec.align == Align::Right),
+        None => s,
+    }
+}
+
+fn render_nodes(nodes: &[Node], index: usize, n: &Note, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(s) => out.push_str(s),
+            Node::Field(name, spec) => out.push_str(&apply(spec, field(name, index, n))),
+            Node::Cond {
+                field: name,
+                negated,
+                body,
+            } => {
+                if field(name, index, n).is_empty() == *negated {
+                    render_nodes(body, index, n, out);
+                }
+            }
+        }
+    }
+}
+
+/// Parses the template given on the command line, or the one in the config file if there's none.
+pub fn resolve(arg: Option<&str>, config: Option<&str>) -> Result<Option<Template>, String> {
+    match (arg, config) {
+        (Some(s), _) => s.parse().map(Some),
+        (None, Some(s)) => s
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("list_template in the config file: {}", e)),
+        (None, None) => Ok(None),
+    }
+}
+
+impl Template {
+    /// Renders the note at `index`.
+    pub fn render(&self, index: usize, n: &Note) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, index, n, &mut out);
+        out
+    }
+
+    /// Prints every note on its own line, in the same order as [note::print_notes_enumerated].
+    pub fn print_notes(&self, notes: &[&(usize, Note)]) {
+        if notes.is_empty() {
+            println!("no results");
+            return;
+        }
+        for (i, n) in notes.iter().rev() {
+            println!("{}", self.render(*i, n));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::date;
+
+    fn render(template: &str, n: &Note) -> String {
+        template.parse::<Template>().unwrap().render(3, n)
+    }
+
+    fn err(template: &str) -> String {
+        template.parse::<Template>().unwrap_err()
+    }
+
+    fn note(title: &str) -> Note {
+        Note::new(title, "")
+    }
+
+    #[test]
+    fn braces_and_escapes() {
+        let n = note("milk");
+        assert_eq!(render("{{{title}}}", &n), "{milk}");
+        assert_eq!(render("{{title}}", &n), "{title}");
+        assert_eq!(render(r"{index}\t{title}\n\\", &n), "3\tmilk\n\\");
+        assert!(err(r"\x").contains("unknown escape"));
+        assert!(err("a } b").contains("unmatched }"));
+    }
+
+    #[test]
+    fn width_and_alignment() {
+        let n = note("milk");
+        assert_eq!(render("[{title:6}]", &n), "[milk  ]");
+        assert_eq!(render("[{title:<6}]", &n), "[milk  ]");
+        assert_eq!(render("[{title:>6}]", &n), "[  milk]");
+        assert_eq!(
+            render("[{title:>20}]", &n),
+            format!("[{}milk]", " ".repeat(16))
+        );
+        // a width doesn't cut anything
+        assert_eq!(render("[{title:2}]", &n), "[milk]");
+        assert!(err("{title:x}").contains("invalid format"));
+        assert!(err("{title:.}").contains("invalid format"));
+    }
+
+    #[test]
+    fn truncation() {
+        let n = note("buy some milk");
+        assert_eq!(render("[{title:.20}]", &n), "[buy some milk]");
+        assert_eq!(render("[{title:.8}]", &n), "[buy som…]");
+        assert_eq!(render("[{title:10.8}]", &n), "[buy som…  ]");
+        assert_eq!(render("[{title:>10.8}]", &n), "[  buy som…]");
+        assert_eq!(render("[{title:20.20}]", &n), "[buy some milk       ]");
+    }
+
+    #[test]
+    fn truncation_counts_columns() {
+        // each of these characters is two columns wide
+        let n = note("日本語のメモ");
+        assert_eq!(render("[{title:.5}]", &n), "[日本…]");
+        assert_eq!(render("[{title:6.5}]", &n), "[日本… ]");
+        assert_eq!(render("[{title:.12}]", &n), "[日本語のメモ]");
+        let n = note("café crème");
+        assert_eq!(render("[{title:.6}]", &n), "[café …]");
+        assert_eq!(render("[{title:>8.6}]", &n), "[  café …]");
+    }
+
+    #[test]
+    fn nested_conditionals() {
+        let t = "{?lvl}!{lvl}{?due} due {due}{/due}{/lvl}{!lvl}-{!tags} untagged{/tags}{/lvl}";
+        let mut n = note("milk");
+        assert_eq!(render(t, &n), "- untagged");
+        n.tags = Some(vec!["home".to_owned()]);
+        assert_eq!(render(t, &n), "-");
+        n.lvl = Some(2);
+        assert_eq!(render(t, &n), "!2");
+        n.due = date::parse_due("2030-01-02 10:00", note::now());
+        assert_eq!(render(t, &n), "!2 due 2030-01-02 10:00");
+    }
+
+    #[test]
+    fn unclosed_and_mismatched_tags() {
+        assert_eq!(
+            err("ab {?lvl}x"),
+            "template error at column 4: {?lvl} is never closed with {/lvl}"
+        );
+        assert_eq!(
+            err("{?lvl}{?due}x{/lvl}{/due}"),
+            "template error at column 14: expected {/due}, found {/lvl}"
+        );
+        assert_eq!(
+            err("x{/lvl}"),
+            "template error at column 2: {/lvl} closes nothing"
+        );
+        assert!(err("{title").contains("unclosed {"));
+        assert!(err("{title {id}").contains("unclosed {"));
+        assert!(err("{nope}").contains("unknown field"));
+        assert!(err("{?nope}{/nope}").contains("unknown field"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Syncs two clones of a todos file through a local bare repository.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+fn git(dir: &Path, home: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", home)
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
+
+/// Writes the config of a clone, with the git options in `git`.
+fn config(root: &Path, clone: &str, git: &str) -> PathBuf {
+    let conf = root.join(format!("conf-{}", clone));
+    fs::create_dir_all(&conf).unwrap();
+    fs::write(
+        conf.join("todo.toml"),
+        format!(
+            "todos_file = {:?}\n[hooks]\n[git]\nauto_commit = true\npull = true\npush = true\n{}",
+            root.join(clone).join("todos.toml"),
+            git
+        ),
+    )
+    .unwrap();
+    conf
+}
+
+fn todo(root: &Path, conf: &Path, args: &[&str]) {
+    let out = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .args(args)
+        .current_dir(root)
+        .env("HOME", root)
+        .env("TODO_CONFIG_DIR", conf)
+        .env_remove("TODO_LIST")
+        .env_remove("TODO_GLOBAL")
+        .env_remove("TODOS_FILE_PATH")
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .stdout(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "todo {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn changes_are_committed_pushed_and_pulled() {
+    let root = env::temp_dir().join(format!("todo-git-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join(".gitconfig"),
+        "[user]\nname = todo\nemail = todo@example.com\n[init]\ndefaultBranch = main\n",
+    )
+    .unwrap();
+
+    git(&root, &root, &["init", "--quiet", "--bare", "remote.git"]);
+    git(&root, &root, &["clone", "--quiet", "remote.git", "a"]);
+    let a = root.join("a");
+    fs::write(a.join("readme"), "todos\n").unwrap();
+    git(&a, &root, &["add", "readme"]);
+    git(&a, &root, &["commit", "--quiet", "-m", "init"]);
+    git(&a, &root, &["push", "--quiet", "-u", "origin", "HEAD"]);
+    git(&root, &root, &["clone", "--quiet", "remote.git", "b"]);
+
+    // a uses the upstream of its branch, b names the remote
+    let conf_a = config(&root, "a", "");
+    let conf_b = config(&root, "b", "remote = \"origin\"\n");
+
+    todo(&root, &conf_a, &["new", "from a", "body"]);
+    todo(&root, &conf_b, &["new", "from b", "body"]);
+    todo(&root, &conf_a, &["new", "again from a", "body"]);
+
+    let remote = root.join("remote.git");
+    let log = git(&remote, &root, &["log", "--format=%s", "main"]);
+    assert_eq!(
+        log.lines().collect::<Vec<_>>(),
+        ["new: again from a", "new: from b", "new: from a", "init"]
+    );
+
+    // each clone pulled the other's change before making its own
+    let todos_a = fs::read_to_string(a.join("todos.toml")).unwrap();
+    assert!(todos_a.contains("title = 'from b'"));
+    let todos_b = fs::read_to_string(root.join("b").join("todos.toml")).unwrap();
+    assert!(todos_b.contains("title = 'from a'"));
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,109 @@
+//! Moves notes between two lists, and undoes and redoes the moves.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+fn setup(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("todo-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("todo.toml"),
+        format!(
+            "todos_file = {:?}\nlock_timeout = 30\n[lists]\nwork = {:?}\n[hooks]\n",
+            dir.join("main.toml"),
+            dir.join("work.toml")
+        ),
+    )
+    .unwrap();
+    dir
+}
+
+fn todo(dir: &Path, list: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_todo"));
+    cmd.args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("TODO_CONFIG_DIR", dir)
+        .env("TODO_LIST", list)
+        .env_remove("TODO_GLOBAL")
+        .env_remove("TODOS_FILE_PATH")
+        .stdout(Stdio::null());
+    cmd
+}
+
+fn run(dir: &Path, list: &str, args: &[&str]) {
+    let out = todo(dir, list, args).output().unwrap();
+    assert!(
+        out.status.success(),
+        "todo {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+fn has(dir: &Path, file: &str, title: &str) -> bool {
+    fs::read_to_string(dir.join(file))
+        .unwrap()
+        .contains(&format!("title = '{}'", title))
+}
+
+#[test]
+fn undoing_a_move_restores_both_lists() {
+    let dir = setup("move-undo");
+    run(&dir, "default", &["new", "a", "body"]);
+    run(&dir, "default", &["new", "b", "body"]);
+
+    run(&dir, "default", &["move", "--to", "work", "b"]);
+    assert!(!has(&dir, "main.toml", "b") && has(&dir, "work.toml", "b"));
+
+    run(&dir, "default", &["undo"]);
+    assert!(has(&dir, "main.toml", "b") && !has(&dir, "work.toml", "b"));
+    assert!(has(&dir, "main.toml", "a"));
+
+    run(&dir, "default", &["redo"]);
+    assert!(!has(&dir, "main.toml", "b") && has(&dir, "work.toml", "b"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn opposite_moves_at_once_both_finish() {
+    let dir = setup("move-opposite");
+    for i in 0..5 {
+        run(&dir, "default", &["new", &format!("main {}", i), "body"]);
+        run(&dir, "work", &["new", &format!("work {}", i), "body"]);
+    }
+
+    let children: Vec<_> = (0..5)
+        .flat_map(|i| {
+            vec![
+                todo(
+                    &dir,
+                    "default",
+                    &["move", "--to", "work", &format!("main {}", i)],
+                )
+                .spawn()
+                .unwrap(),
+                todo(
+                    &dir,
+                    "work",
+                    &["move", "--to", "default", &format!("work {}", i)],
+                )
+                .spawn()
+                .unwrap(),
+            ]
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    for i in 0..5 {
+        assert!(has(&dir, "work.toml", &format!("main {}", i)));
+        assert!(has(&dir, "main.toml", &format!("work {}", i)));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
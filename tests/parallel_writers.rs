@@ -0,0 +1,51 @@
+//! Runs several todo processes that change the same todos file at once.
+
+use std::{
+    env, fs,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn parallel_writers_lose_no_note() {
+    let dir = env::temp_dir().join(format!("todo-parallel-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let todos = dir.join("todos.toml");
+    fs::write(
+        dir.join("todo.toml"),
+        format!("todos_file = {:?}\nlock_timeout = 30\n[hooks]\n", todos),
+    )
+    .unwrap();
+    // the processes all start by creating the todos file, none of them may truncate it after another saved notes
+    assert!(!todos.exists());
+
+    let n = 20;
+    let children: Vec<_> = (0..n)
+        .map(|i| {
+            Command::new(env!("CARGO_BIN_EXE_todo"))
+                .args(["new", &format!("note {}", i), "body"])
+                .current_dir(&dir)
+                .env("HOME", &dir)
+                .env("TODO_CONFIG_DIR", &dir)
+                .env_remove("TODO_LIST")
+                .env_remove("TODO_GLOBAL")
+                .env_remove("TODOS_FILE_PATH")
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let data = fs::read_to_string(&todos).unwrap();
+    for i in 0..n {
+        assert!(
+            data.contains(&format!("title = 'note {}'", i)),
+            "note {} was lost",
+            i
+        );
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,58 @@
+//! Uses the project todos file only when it's enabled in the config.
+
+use std::{env, fs, path::Path, process::Command};
+
+fn todo(dir: &Path, cwd: &Path, args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .args(args)
+        .current_dir(cwd)
+        .env("HOME", dir)
+        .env("TODO_CONFIG_DIR", dir)
+        .env_remove("TODO_LIST")
+        .env_remove("TODO_GLOBAL")
+        .env_remove("TODOS_FILE_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "todo {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
+
+fn config(dir: &Path, extra: &str) {
+    fs::write(
+        dir.join("todo.toml"),
+        format!(
+            "todos_file = {:?}\n{}[hooks]\n",
+            dir.join("main.toml"),
+            extra
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn project_todos_file_is_used_only_when_enabled() {
+    let dir = env::temp_dir().join(format!("todo-project-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let project = dir.join("project").join("src");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(dir.join("project").join(".todos.toml"), "").unwrap();
+
+    config(&dir, "");
+    todo(&dir, &project, &["new", "global task", "body"]);
+    let out = todo(&dir, &project, &["where"]);
+    assert!(out.contains("main.toml"), "{}", out);
+    assert!(!out.contains(".todos.toml"), "{}", out);
+
+    config(&dir, "project_todos = true\n");
+    todo(&dir, &project, &["new", "project task", "body"]);
+    let out = todo(&dir, &project, &["where"]);
+    assert!(out.contains(".todos.toml"), "{}", out);
+    let out = todo(&dir, &project, &["list"]);
+    assert!(out.contains("project task") && !out.contains("global task"));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,55 @@
+//! Displays views with the global options after the view name.
+
+use std::{env, fs, path::Path, process::Command};
+
+fn todo(dir: &Path, args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("TODO_CONFIG_DIR", dir)
+        .env_remove("TODO_LIST")
+        .env_remove("TODO_GLOBAL")
+        .env_remove("TODOS_FILE_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "todo {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn view_takes_the_list_option_after_its_name() {
+    let dir = env::temp_dir().join(format!("todo-views-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("todo.toml"),
+        format!(
+            "todos_file = {:?}\n[lists]\nwork = {:?}\n[views.urgent]\nlvl = \"3\"\n[hooks]\n",
+            dir.join("main.toml"),
+            dir.join("work.toml")
+        ),
+    )
+    .unwrap();
+    todo(&dir, &["new", "-l", "3", "home chore", "body"]);
+    todo(&dir, &["-L", "work", "new", "-l", "3", "work task", "body"]);
+
+    for args in [
+        &["list", "@urgent", "-L", "work"][..],
+        &["list", "@urgent", "--list=work"],
+        &["-L", "work", "list", "@urgent"],
+        &["view", "urgent", "-L", "work"],
+    ] {
+        let out = todo(&dir, args);
+        assert!(out.contains("work task"), "todo {:?}: {}", args, out);
+        assert!(!out.contains("home chore"), "todo {:?}: {}", args, out);
+    }
+    let out = todo(&dir, &["list", "@urgent"]);
+    assert!(out.contains("home chore") && !out.contains("work task"));
+    fs::remove_dir_all(&dir).unwrap();
+}